@@ -10,7 +10,8 @@ use msp430fr2x5x_hal::{
     fram::Fram,
     gpio::Batch,
     pmm::Pmm,
-    watchdog::{Wdt, WdtClkPeriods},
+    time::Microseconds,
+    watchdog::Wdt,
 };
 use nb::block;
 use panic_msp430 as _;
@@ -29,13 +30,13 @@ fn main() -> ! {
         .split(&pmm);
     let mut p1_0 = p1.pin0;
 
-    let (smclk, _aclk) = ClockConfig::new(periph.CS)
+    let (_mclk, smclk, _aclk, _delay) = ClockConfig::new(periph.CS)
         .mclk_dcoclk(DcoclkFreqSel::_8MHz, MclkDiv::_1)
         .smclk_on(SmclkDiv::_1)
         .aclk_vloclk()
         .freeze(&mut fram);
 
-    const DELAY: WdtClkPeriods = WdtClkPeriods::_8192K;
+    const DELAY: Microseconds = Microseconds::secs(1);
 
     // blinks should be 1 second on, 1 second off
     let mut wdt = wdt.to_interval();
@@ -5,11 +5,12 @@ use embedded_hal::digital::v2::OutputPin;
 use embedded_hal::prelude::*;
 use msp430_rt::entry;
 use msp430fr2x5x_hal::{
-    clock::{ClockConfig, DcoclkFreqSel, MclkDiv, SmclkDiv},
+    clock::{ClockConfig, ClockTree, DcoclkFreqSel, MclkDiv, SmclkDefined, SmclkDiv},
     fram::Fram,
     gpio::Batch,
     pmm::Pmm,
     serial::*,
+    time::Hertz,
     watchdog::Wdt,
 };
 use nb::block;
@@ -20,6 +21,13 @@ use panic_msp430 as _;
 #[cfg(not(debug_assertions))]
 use panic_never as _;
 
+// Checked at compile time, so an ACLK too slow for the baud rate fails the build. The same tree
+// configures the clocks, so the checked frequencies are the ones actually used.
+const CLOCKS: ClockTree<SmclkDefined> = ClockTree::mclk_dcoclk(DcoclkFreqSel::_1MHz, MclkDiv::_1)
+    .smclk_on(SmclkDiv::_2)
+    .aclk_refoclk();
+const BAUDRATE: Hertz = validate_baudrate(CLOCKS.aclk(), Hertz::from_raw(9600));
+
 // Prints "HELLO" when started then echos on UART1
 // Serial settings are listed in the code
 #[entry]
@@ -28,10 +36,8 @@ fn main() -> ! {
         let mut fram = Fram::new(periph.FRCTL);
         let _wdt = Wdt::constrain(periph.WDT_A);
 
-        let (_smclk, aclk) = ClockConfig::new(periph.CS)
-            .mclk_dcoclk(DcoclkFreqSel::_1MHz, MclkDiv::_1)
-            .smclk_on(SmclkDiv::_2)
-            .aclk_refoclk()
+        let (_mclk, _smclk, aclk, _delay) = ClockConfig::new(periph.CS)
+            .clock_tree(CLOCKS)
             .freeze(&mut fram);
 
         let pmm = Pmm::new(periph.PMM);
@@ -47,7 +53,7 @@ fn main() -> ! {
             // Launchpad UART-to-USB converter doesn't handle parity, so we don't use it
             Parity::NoParity,
             Loopback::NoLoop,
-            9600,
+            BAUDRATE,
         )
         .use_aclk(&aclk)
         .split(p4.pin3.to_alternate1(), p4.pin2.to_alternate1());
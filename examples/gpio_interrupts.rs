@@ -15,7 +15,8 @@ use msp430fr2x5x_hal::{
     fram::Fram,
     gpio::{Batch, GpioVector, Output, Pin, Pin0, PxIV, P1, P2},
     pmm::Pmm,
-    watchdog::{Wdt, WdtClkPeriods},
+    time::ExtU32,
+    watchdog::Wdt,
 };
 use nb::block;
 use panic_msp430 as _;
@@ -28,7 +29,7 @@ static P2IV: Mutex<RefCell<Option<PxIV<P2>>>> = Mutex::new(RefCell::new(None));
 #[entry]
 fn main() -> ! {
     let periph = msp430fr2355::Peripherals::take().unwrap();
-    let (_smclk, aclk) = ClockConfig::new(periph.CS)
+    let (_mclk, _smclk, aclk, _delay) = ClockConfig::new(periph.CS)
         .mclk_refoclk(MclkDiv::_1)
         // 32 KHz SMCLK
         .smclk_on(SmclkDiv::_2)
@@ -56,9 +57,7 @@ fn main() -> ! {
     with(|cs| *RED_LED.borrow(cs).borrow_mut() = Some(red_led));
     with(|cs| *P2IV.borrow(cs).borrow_mut() = Some(p2iv));
 
-    wdt.set_aclk(&aclk)
-        .enable_interrupts()
-        .start(WdtClkPeriods::_32K);
+    wdt.set_aclk(&aclk).enable_interrupts().start(2.secs());
     pin.select_rising_edge_trigger().enable_interrupts();
     button.select_falling_edge_trigger();
 
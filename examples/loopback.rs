@@ -11,6 +11,7 @@ use msp430fr2x5x_hal::{
     gpio::Batch,
     pmm::Pmm,
     serial::*,
+    time::{Hertz, RateExtU32},
     watchdog::Wdt,
 };
 use nb::block;
@@ -22,9 +23,9 @@ fn setup_uart<S: SerialUsci>(
     rx: S::RxPin,
     parity: Parity,
     loopback: Loopback,
-    baudrate: u32,
+    baudrate: Hertz,
     smclk: &Smclk,
-) -> (Tx<S>, Rx<S>) {
+) -> (Tx<'_, S>, Rx<'_, S>) {
     SerialConfig::new(
         usci,
         BitOrder::LsbFirst,
@@ -54,7 +55,7 @@ fn main() -> ! {
     let mut fram = Fram::new(periph.FRCTL);
     let _wdt = Wdt::constrain(periph.WDT_A);
 
-    let (smclk, _aclk) = ClockConfig::new(periph.CS)
+    let (_mclk, smclk, _aclk, _delay) = ClockConfig::new(periph.CS)
         .mclk_dcoclk(DcoclkFreqSel::_4MHz, MclkDiv::_1)
         .smclk_on(SmclkDiv::_2)
         .aclk_refoclk()
@@ -72,7 +73,7 @@ fn main() -> ! {
         p1.pin6.to_alternate1().into(),
         Parity::EvenParity,
         Loopback::Loopback,
-        20000,
+        20000.Hz(),
         &smclk,
     );
 
@@ -82,7 +83,7 @@ fn main() -> ! {
         p4.pin2.to_alternate1().into(),
         Parity::NoParity,
         Loopback::NoLoop,
-        19200,
+        19200.Hz(),
         &smclk,
     );
 
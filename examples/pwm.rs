@@ -9,6 +9,7 @@ use msp430fr2x5x_hal::{
     gpio::Batch,
     pmm::Pmm,
     pwm::{Pwm, PwmParts7, PwmPeriph, TimerConfig},
+    time::RateExtU32,
     watchdog::Wdt,
 };
 use panic_msp430 as _;
@@ -24,13 +25,13 @@ fn main() -> ! {
     let pmm = Pmm::new(periph.PMM);
     let p6 = Batch::new(periph.P6).split(&pmm);
 
-    let (smclk, _aclk) = ClockConfig::new(periph.CS)
+    let (_mclk, smclk, _aclk, _delay) = ClockConfig::new(periph.CS)
         .mclk_dcoclk(DcoclkFreqSel::_1MHz, MclkDiv::_1)
         .smclk_on(SmclkDiv::_1)
         .aclk_vloclk()
         .freeze(&mut fram);
 
-    let pwm = PwmParts7::new(periph.TB3, TimerConfig::smclk(&smclk), 5000);
+    let pwm = PwmParts7::new(periph.TB3, TimerConfig::smclk(&smclk), 200.Hz());
     let mut pwm4 = pwm.pwm4.init(p6.pin3.to_output().to_alternate1());
     let mut pwm5 = pwm.pwm5.init(p6.pin4.to_output().to_alternate1());
 
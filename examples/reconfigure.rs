@@ -0,0 +1,61 @@
+#![no_main]
+#![no_std]
+
+use embedded_hal::digital::v2::*;
+use embedded_hal::prelude::*;
+use msp430_rt::entry;
+use msp430fr2x5x_hal::{
+    clock::{ClockConfig, DcoclkFreqSel, MclkDiv, SmclkDiv},
+    fram::Fram,
+    gpio::Batch,
+    pmm::Pmm,
+    watchdog::Wdt,
+};
+use panic_msp430 as _;
+
+// Red LED blinks with the same delay while MCLK runs at 24 MHz and after MCLK drops to 1 MHz.
+// The blink rate staying the same shows the delay provider tracking the new frequency.
+#[entry]
+fn main() -> ! {
+    let periph = msp430fr2355::Peripherals::take().unwrap();
+
+    let mut fram = Fram::new(periph.FRCTL);
+    let _wdt = Wdt::constrain(periph.WDT_A);
+
+    let pmm = Pmm::new(periph.PMM);
+    let p1 = Batch::new(periph.P1)
+        .config_pin0(|p| p.to_output())
+        .split(&pmm);
+    let mut led = p1.pin0;
+
+    let (mclk, smclk, aclk, mut delay) = ClockConfig::new(periph.CS)
+        .mclk_dcoclk(DcoclkFreqSel::_24MHz, MclkDiv::_1)
+        .smclk_on(SmclkDiv::_1)
+        .freeze(&mut fram);
+
+    for _ in 0..10 {
+        led.toggle().ok();
+        delay.delay_ms(500u16);
+    }
+
+    // Drop to 1 MHz. The old clock objects are consumed, so they can't be reused. Peripherals
+    // borrow the clock object they were configured from, so any of them would have to be dropped
+    // or freed first.
+    let (_mclk, _smclk, _aclk, mut delay) = ClockConfig::reconfigure((mclk, smclk, aclk, delay))
+        .mclk_dcoclk(DcoclkFreqSel::_1MHz, MclkDiv::_1)
+        .smclk_on(SmclkDiv::_1)
+        .freeze(&mut fram);
+
+    loop {
+        led.toggle().ok();
+        delay.delay_ms(500u16);
+    }
+}
+
+// The compiler will emit calls to the abort() compiler intrinsic if debug assertions are
+// enabled (default for dev profile). MSP430 does not actually have meaningful abort() support
+// so for now, we create our own in each application where debug assertions are present.
+#[no_mangle]
+extern "C" fn abort() -> ! {
+    panic!();
+}
@@ -11,6 +11,7 @@ use msp430fr2x5x_hal::{
     gpio::Batch,
     pmm::Pmm,
     rtc::{Rtc, RtcDiv},
+    time::ExtU32,
     watchdog::Wdt,
 };
 use panic_msp430 as _;
@@ -33,7 +34,7 @@ fn main() -> ! {
     let mut led = p1.pin0;
     let mut button = p2.pin3;
 
-    let (_smclk, _aclk) = ClockConfig::new(periph.CS)
+    let (_mclk, _smclk, _aclk, _delay) = ClockConfig::new(periph.CS)
         .mclk_refoclk(MclkDiv::_1)
         .smclk_on(SmclkDiv::_1)
         .aclk_vloclk()
@@ -47,7 +48,7 @@ fn main() -> ! {
 
     loop {
         // 2 seconds
-        rtc.start(2000u16);
+        rtc.start(2.secs());
         while let Err(nb::Error::WouldBlock) = rtc.wait() {
             if let Ok(_) = button.wait_for_ifg() {
                 led.toggle().ok();
@@ -0,0 +1,58 @@
+#![no_main]
+#![no_std]
+#![feature(abi_msp430_interrupt)]
+#![feature(asm_experimental_arch)]
+
+use embedded_hal::digital::v2::*;
+use embedded_hal::prelude::*;
+use msp430_rt::entry;
+use msp430fr2355::interrupt;
+use msp430fr2x5x_hal::{
+    clock::{ClockConfig, MclkDiv, SmclkDiv},
+    fram::Fram,
+    gpio::Batch,
+    pmm::Pmm,
+    rtc::{self, Rtc, RtcDelay},
+    watchdog::Wdt,
+};
+use panic_msp430 as _;
+
+// Red LED blinks 2 seconds on, 2 off, with the CPU asleep in LPM3 in between
+#[entry]
+fn main() -> ! {
+    let periph = msp430fr2355::Peripherals::take().unwrap();
+
+    Wdt::constrain(periph.WDT_A);
+
+    let pmm = Pmm::new(periph.PMM);
+    let p1 = Batch::new(periph.P1)
+        .config_pin0(|p| p.to_output())
+        .split(&pmm);
+    let mut led = p1.pin0;
+
+    let (_mclk, _smclk, _aclk, _delay) = ClockConfig::new(periph.CS)
+        .mclk_refoclk(MclkDiv::_1)
+        .smclk_on(SmclkDiv::_1)
+        .aclk_vloclk()
+        .freeze(&mut Fram::new(periph.FRCTL));
+
+    let mut delay = RtcDelay::new(Rtc::new(periph.RTC));
+
+    loop {
+        led.toggle().ok();
+        delay.delay_ms(2000u32);
+    }
+}
+
+#[interrupt(wake_cpu)]
+fn RTC() {
+    rtc::on_delay_interrupt();
+}
+
+// The compiler will emit calls to the abort() compiler intrinsic if debug assertions are
+// enabled (default for dev profile). MSP430 does not actually have meaningful abort() support
+// so for now, we create our own in each application where debug assertions are present.
+#[no_mangle]
+extern "C" fn abort() -> ! {
+    panic!();
+}
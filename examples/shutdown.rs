@@ -0,0 +1,57 @@
+#![no_main]
+#![no_std]
+#![feature(abi_msp430_interrupt)]
+
+use embedded_hal::digital::v2::*;
+use msp430_rt::entry;
+use msp430fr2355::interrupt;
+use msp430fr2x5x_hal::{gpio::Batch, pmm::Pmm, watchdog::Wdt};
+use panic_msp430 as _;
+
+// Shuts down into LPM4.5 until P2.3 is pressed, then toggles the red LED once awake.
+// The red LED stays lit throughout the shutdown.
+#[entry]
+fn main() -> ! {
+    let periph = msp430fr2355::Peripherals::take().unwrap();
+    let _wdt = Wdt::constrain(periph.WDT_A);
+
+    let pmm = Pmm::new_locked(periph.PMM);
+    let woke = pmm.woke_from_lpm5();
+
+    // Restore the pin configuration from before the shutdown before unlocking the GPIOs
+    let p1 = Batch::new(periph.P1)
+        .config_pin0(|p| p.to_output())
+        .split_locked(&pmm);
+    let p2 = Batch::new(periph.P2)
+        .config_pin3(|p| p.pullup())
+        .split_locked(&pmm);
+    let mut red_led = p1.pin0;
+    let mut button = p2.pin3;
+    button.select_falling_edge_trigger().enable_interrupts();
+
+    let pmm = pmm.unlock();
+    if woke {
+        red_led.toggle().ok();
+        // Service the button press that woke us up
+        unsafe { msp430::interrupt::enable() };
+    } else {
+        red_led.set_high().ok();
+    }
+
+    pmm.enter_lpm4_5();
+}
+
+#[interrupt]
+fn PORT2() {
+    // Clear the interrupt flag so the shutdown isn't aborted straight away
+    let p2 = unsafe { &*msp430fr2355::P2::ptr() };
+    p2.p2iv.read();
+}
+
+// The compiler will emit calls to the abort() compiler intrinsic if debug assertions are
+// enabled (default for dev profile). MSP430 does not actually have meaningful abort() support
+// so for now, we create our own in each application where debug assertions are present.
+#[no_mangle]
+extern "C" fn abort() -> ! {
+    panic!();
+}
@@ -9,6 +9,7 @@ use msp430fr2x5x_hal::{
     fram::Fram,
     gpio::Batch,
     pmm::Pmm,
+    time::{ExtU32, Microseconds},
     timer::{CapCmp, SubTimer, Timer, TimerConfig, TimerDiv, TimerExDiv, TimerParts3, TimerPeriph},
     watchdog::Wdt,
 };
@@ -30,7 +31,7 @@ fn main() -> ! {
         .split(&pmm);
     let mut p1_0 = p1.pin0;
 
-    let (_smclk, aclk) = ClockConfig::new(periph.CS)
+    let (_mclk, _smclk, aclk, _delay) = ClockConfig::new(periph.CS)
         .mclk_dcoclk(DcoclkFreqSel::_1MHz, MclkDiv::_1)
         .smclk_on(SmclkDiv::_1)
         .aclk_vloclk()
@@ -43,7 +44,7 @@ fn main() -> ! {
     let mut timer = parts.timer;
     let mut subtimer = parts.subtimer2;
 
-    set_time(&mut timer, &mut subtimer, 500);
+    set_time(&mut timer, &mut subtimer, 500.millis());
     loop {
         block!(subtimer.wait()).void_unwrap();
         p1_0.set_high().void_unwrap();
@@ -57,10 +58,10 @@ fn main() -> ! {
 fn set_time<T: TimerPeriph + CapCmp<C>, C>(
     timer: &mut Timer<T>,
     subtimer: &mut SubTimer<T, C>,
-    delay: u16,
+    delay: Microseconds,
 ) {
     timer.start(delay + delay);
-    subtimer.set_count(delay);
+    subtimer.set_count(delay).unwrap();
 }
 
 // The compiler will emit calls to the abort() compiler intrinsic if debug assertions are
@@ -2,5 +2,22 @@ MEMORY
 {
   RAM : ORIGIN = 0x2000, LENGTH = 0x1000
   ROM : ORIGIN = 0x8000, LENGTH = 0x7F80
+  JTAGSIG : ORIGIN = 0xFF80, LENGTH = 0x24
   VECTORS : ORIGIN = 0xFFA4, LENGTH = 0x5C
 }
+
+SECTIONS
+{
+  /* Persistent FRAM variables, which are not initialized at startup */
+  .persistent : ALIGN(2)
+  {
+    *(.persistent .persistent.*);
+    . = ALIGN(2);
+  } > ROM
+
+  /* JTAG lock signature, only programmed when `jtag_lock!` is used */
+  .jtag_signature :
+  {
+    KEEP(*(.jtag_signature));
+  } > JTAGSIG
+} INSERT AFTER .rodata;
@@ -1,4 +1,7 @@
 use crate::gpio::*;
+use crate::pmm::{self, RefVoltage, Reference};
+use crate::tlv::{AdcCalibration, Tlv};
+use core::marker::PhantomData;
 use core::u8;
 use embedded_hal::adc::{Channel, OneShot};
 use msp430fr2355::ADC;
@@ -127,7 +130,7 @@ impl SamplingRate {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin0, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin0, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -135,7 +138,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin0, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin1, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin1, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -143,7 +146,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin1, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin2, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin2, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -151,7 +154,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin2, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin3, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin3, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -159,7 +162,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin3, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin4, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin4, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -167,7 +170,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin4, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin5, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin5, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -175,7 +178,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin5, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin6, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin6, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -183,7 +186,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin6, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P1, Pin7, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P1, Pin7, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -191,7 +194,7 @@ impl Channel<Adc<ADC>> for Pin<P1, Pin7, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P5, Pin0, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P5, Pin0, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -199,7 +202,7 @@ impl Channel<Adc<ADC>> for Pin<P5, Pin0, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P5, Pin1, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P5, Pin1, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -207,7 +210,7 @@ impl Channel<Adc<ADC>> for Pin<P5, Pin1, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P5, Pin2, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P5, Pin2, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -215,7 +218,7 @@ impl Channel<Adc<ADC>> for Pin<P5, Pin2, Alternate3<Input<Floating>>> {
     }
 }
 
-impl Channel<Adc<ADC>> for Pin<P5, Pin3, Alternate3<Input<Floating>>> {
+impl Channel<Adc<'_, ADC>> for Pin<P5, Pin3, Alternate3<Input<Floating>>> {
     type ID = u8;
 
     fn channel() -> Self::ID {
@@ -223,9 +226,62 @@ impl Channel<Adc<ADC>> for Pin<P5, Pin3, Alternate3<Input<Floating>>> {
     }
 }
 
-pub struct Adc<ADC> {
+/// Internal temperature sensor, sampled on ADC channel 12 with `Adc::read_temperature`. The sensor
+/// is calibrated against the 1.5 V internal reference, which must stay on while the sensor is in
+/// use.
+pub struct TempSensor<'a> {
+    _reference: &'a Reference,
+    cal: AdcCalibration,
+}
+
+/// Reasons the temperature sensor can't be used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TempSensorError {
+    /// The reference isn't set to 1.5 V
+    WrongReference,
+    /// The TLV holds no ADC calibration
+    NoCalibration,
+    /// The 105 C calibration value isn't above the 30 C one
+    BadCalibration,
+}
+
+impl<'a> TempSensor<'a> {
+    // ADC channel of the sensor. The sensor is only sampled through `Adc::read_temperature`, which
+    // sets up the reference and sample time it needs, so it doesn't implement `Channel`.
+    const CHANNEL: u8 = 12;
+
+    /// Turns on the temperature sensor. Fails if the reference isn't set to 1.5 V, or if the TLV
+    /// holds no usable ADC calibration.
+    pub fn new(reference: &'a Reference, tlv: &Tlv) -> Result<Self, TempSensorError> {
+        if reference.voltage() != RefVoltage::_1V5 {
+            return Err(TempSensorError::WrongReference);
+        }
+        let cal = tlv
+            .adc_calibration()
+            .ok_or(TempSensorError::NoCalibration)?;
+        // `Adc::to_celsius` divides by the difference
+        if cal.temp_105c <= cal.temp_30c {
+            return Err(TempSensorError::BadCalibration);
+        }
+        pmm::set_temp_sensor(true);
+        Ok(TempSensor {
+            _reference: reference,
+            cal,
+        })
+    }
+
+    /// Turns off the temperature sensor
+    pub fn free(self) {
+        pmm::set_temp_sensor(false);
+    }
+}
+
+/// ADC, which borrows the internal reference while converting against it, so the reference can't
+/// be turned off underneath it
+pub struct Adc<'a, ADC> {
     adc_reg: ADC,
     is_waiting: bool,
+    _reference: PhantomData<&'a Reference>,
 }
 
 pub struct AdcConfig {
@@ -259,7 +315,7 @@ impl AdcConfig {
         }
     }
 
-    pub fn config_hw(self) -> Adc<ADC> {
+    pub fn config_hw(self) -> Adc<'static, ADC> {
         let adc_reg = self.adc;
 
         adc_reg
@@ -289,15 +345,17 @@ impl AdcConfig {
         Adc {
             adc_reg,
             is_waiting: false,
+            _reference: PhantomData,
         }
     }
 }
 
-impl Adc<ADC> {
-    pub fn new(adc: ADC) -> Adc<ADC> {
+impl<'a> Adc<'a, ADC> {
+    pub fn new(adc: ADC) -> Adc<'a, ADC> {
         Adc {
             adc_reg: adc,
             is_waiting: false,
+            _reference: PhantomData,
         }
     }
 
@@ -325,9 +383,89 @@ impl Adc<ADC> {
         self.adc_reg.adcmem0.read().bits()
     }
 
+    /// Use the internal reference as the positive conversion reference. The ADC borrows the
+    /// reference until it's switched back to AVCC with `use_avcc` or dropped.
+    pub fn use_reference(mut self, _reference: &Reference) -> Adc<'_, ADC> {
+        self.adc_disable();
+        self.adc_reg.adcmctl0.modify(|_, w| w.adcsref().adcsref_1());
+        Adc {
+            adc_reg: self.adc_reg,
+            is_waiting: false,
+            _reference: PhantomData,
+        }
+    }
+
+    /// Use AVCC as the positive conversion reference, releasing the internal reference
+    pub fn use_avcc(mut self) -> Adc<'static, ADC> {
+        self.adc_disable();
+        self.adc_reg.adcmctl0.modify(|_, w| w.adcsref().adcsref_0());
+        Adc {
+            adc_reg: self.adc_reg,
+            is_waiting: false,
+            _reference: PhantomData,
+        }
+    }
+
+    /// Convert a raw result into millivolts, for conversions made against `reference`
+    pub fn to_millivolts(&self, reference: &Reference, count: u16) -> u16 {
+        let bits = match self.adc_reg.adcctl2.read().adcres().bits() {
+            0b00 => 8,
+            0b01 => 10,
+            _ => 12,
+        };
+        ((count as u32 * reference.millivolts() as u32) >> bits) as u16
+    }
+
+    /// Convert a raw temperature sensor result into degrees Celsius, using the factory
+    /// calibration. The conversion must have been made against the internal reference.
+    pub fn to_celsius(&self, sensor: &TempSensor, count: u16) -> i16 {
+        // Calibration values are 12-bit
+        let shift = match self.adc_reg.adcctl2.read().adcres().bits() {
+            0b00 => 4,
+            0b01 => 2,
+            _ => 0,
+        };
+        let count = (count as i32) << shift;
+        let cal30 = sensor.cal.temp_30c as i32;
+        let cal105 = sensor.cal.temp_105c as i32;
+        let span = cal105 - cal30;
+        // Round to the nearest degree
+        let scaled = (count - cal30) * (105 - 30) * 2 + span;
+        (scaled.div_euclid(2 * span) + 30) as i16
+    }
+
+    /// Sample the temperature sensor with the internal reference and a sample time long enough
+    /// for the sensor, then return the temperature in degrees Celsius. The previous reference and
+    /// sample time are restored afterwards.
+    pub fn read_temperature(&mut self, sensor: &TempSensor) -> i16 {
+        self.adc_disable();
+        let adcsht = self.adc_reg.adcctl0.read().adcsht().bits();
+        let adcsref = self.adc_reg.adcmctl0.read().adcsref().bits();
+        // The sensor needs at least 30 us of sampling, which the longest sample time covers at
+        // every ADC clock frequency up to 24 MHz
+        self.adc_reg
+            .adcctl0
+            .modify(|_, w| w.adcsht().bits(SampleTime::_1024.adcsht()));
+        self.adc_reg
+            .adcmctl0
+            .modify(|_, w| w.adcsref().adcsref_1().adcinch().bits(TempSensor::CHANNEL));
+        self.adc_enable();
+        self.adc_start_conversion();
+        while self.adc_is_busy() {}
+        let count = self.adc_get_result();
+
+        self.adc_disable();
+        self.adc_reg.adcctl0.modify(|_, w| w.adcsht().bits(adcsht));
+        self.adc_reg
+            .adcmctl0
+            .modify(|_, w| w.adcsref().bits(adcsref));
+        self.is_waiting = false;
+        self.to_celsius(sensor, count)
+    }
+
     pub fn adc_set_pin<PIN>(&mut self, _pin: &PIN)
     where
-        PIN: Channel<Adc<ADC>, ID = u8>,
+        PIN: Channel<Adc<'a, ADC>, ID = u8>,
     {
         self.adc_reg
             .adcmctl0
@@ -335,10 +473,10 @@ impl Adc<ADC> {
     }
 }
 
-impl<WORD, PIN> OneShot<Adc<ADC>, WORD, PIN> for Adc<ADC>
+impl<'a, WORD, PIN> OneShot<Adc<'a, ADC>, WORD, PIN> for Adc<'a, ADC>
 where
     WORD: From<u16>,
-    PIN: Channel<Adc<ADC>, ID = u8>,
+    PIN: Channel<Adc<'a, ADC>, ID = u8>,
 {
     type Error = ();
 
@@ -0,0 +1,132 @@
+//! Backup memory
+//!
+//! The 32 bytes of backup memory (BAKMEM) keep their contents in LPM3.5, where all other RAM is
+//! lost, so they can carry small amounts of state across a shutdown without wearing FRAM. The
+//! contents are lost on a brownout.
+//!
+//! Besides raw word and byte access, `BakMem::save` and `BakMem::restore` store a `Retained` value
+//! along with a CRC, so that stale or uninitialized contents are detected after a cold start.
+
+use crate::crc::crc16;
+use core::marker::PhantomData;
+use core::ptr;
+
+// Start of backup memory
+const BAKMEM_START: usize = 0x0660;
+/// Size of backup memory in 16-bit words
+pub const WORDS: usize = 16;
+/// Size of backup memory in bytes
+pub const BYTES: usize = WORDS * 2;
+
+/// Backup memory
+pub struct BakMem(());
+
+impl BakMem {
+    // Only handed out once, by `Pmm`
+    #[inline]
+    pub(crate) fn new() -> Self {
+        BakMem(())
+    }
+
+    #[inline(always)]
+    fn base() -> *mut u16 {
+        BAKMEM_START as *mut u16
+    }
+
+    /// Read a word. Panics if `idx` is not below `WORDS`.
+    #[inline]
+    pub fn read_word(&self, idx: usize) -> u16 {
+        assert!(idx < WORDS);
+        unsafe { ptr::read_volatile(Self::base().add(idx)) }
+    }
+
+    /// Write a word. Panics if `idx` is not below `WORDS`.
+    #[inline]
+    pub fn write_word(&mut self, idx: usize, val: u16) {
+        assert!(idx < WORDS);
+        unsafe { ptr::write_volatile(Self::base().add(idx), val) };
+    }
+
+    /// Read a byte. Panics if `idx` is not below `BYTES`.
+    #[inline]
+    pub fn read_byte(&self, idx: usize) -> u8 {
+        assert!(idx < BYTES);
+        unsafe { ptr::read_volatile((Self::base() as *const u8).add(idx)) }
+    }
+
+    /// Write a byte. Panics if `idx` is not below `BYTES`.
+    #[inline]
+    pub fn write_byte(&mut self, idx: usize, val: u8) {
+        assert!(idx < BYTES);
+        unsafe { ptr::write_volatile((Self::base() as *mut u8).add(idx), val) };
+    }
+
+    /// Store `val` in the first `T::WORDS` words, followed by a CRC word. Fails to compile if
+    /// `T::WORDS` leaves no room for the CRC word.
+    pub fn save<T: Retained>(&mut self, val: &T) {
+        let () = Fits::<T>::CHECK;
+        let mut words = [0; WORDS];
+        let words = &mut words[..T::WORDS];
+        val.store(words);
+        for (i, &w) in words.iter().enumerate() {
+            self.write_word(i, w);
+        }
+        self.write_word(T::WORDS, crc_of(words));
+    }
+
+    /// Load a value stored by `save`. Returns `None` if the CRC doesn't match, for example after a
+    /// brownout.
+    pub fn restore<T: Retained>(&self) -> Option<T> {
+        let () = Fits::<T>::CHECK;
+        let mut words = [0; WORDS];
+        let words = &mut words[..T::WORDS];
+        for (i, w) in words.iter_mut().enumerate() {
+            *w = self.read_word(i);
+        }
+        if crc_of(words) == self.read_word(T::WORDS) {
+            Some(T::load(words))
+        } else {
+            None
+        }
+    }
+
+    /// Invalidate any value stored by `save`
+    #[inline]
+    pub fn invalidate<T: Retained>(&mut self) {
+        let () = Fits::<T>::CHECK;
+        let crc = self.read_word(T::WORDS);
+        self.write_word(T::WORDS, !crc);
+    }
+}
+
+// Checks that a `Retained` value and its CRC word fit in backup memory
+struct Fits<T>(PhantomData<T>);
+
+impl<T: Retained> Fits<T> {
+    const CHECK: () = assert!(
+        T::WORDS < WORDS,
+        "Retained value too large for backup memory"
+    );
+}
+
+// CRC of a slice of words, in little-endian byte order
+fn crc_of(words: &[u16]) -> u16 {
+    let mut bytes = [0; BYTES];
+    for (chunk, w) in bytes.chunks_exact_mut(2).zip(words) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    crc16(&bytes[..words.len() * 2])
+}
+
+/// State that can be kept in backup memory across LPM3.5
+pub trait Retained: Sized {
+    /// Number of words used, which must leave room for the CRC word, so at most 15. Larger values
+    /// fail to compile when saved or restored.
+    const WORDS: usize;
+
+    /// Encode the state into `words`, which is `WORDS` long
+    fn store(&self, words: &mut [u16]);
+
+    /// Decode the state from `words`, which is `WORDS` long
+    fn load(words: &[u16]) -> Self;
+}
@@ -11,7 +11,7 @@
 
 use crate::gpio::*;
 use crate::hw_traits::gpio::{GpioPeriph, IntrPeriph};
-use crate::pmm::Pmm;
+use crate::pmm::{LockedPmm, Pmm};
 use crate::util::BitsExt;
 use core::marker::PhantomData;
 
@@ -367,7 +367,23 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
     /// ensured when passing `&Pmm` into the method, since a `Pmm` is created only by setting
     /// LOCKLPM5.
     #[inline]
-    pub fn split(self, _pmm: &Pmm) -> Parts<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7> {
+    pub fn split<SVS>(
+        self,
+        _pmm: &Pmm<SVS>,
+    ) -> Parts<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7> {
+        self.write_regs();
+        Parts::new()
+    }
+
+    /// Commits all pin configurations to GPIO registers while LOCKLPM5 is still set, so the pins
+    /// keep the state they held during a LPM3.5 or LPM4.5 shutdown. The new configuration,
+    /// including any interrupt enables set on the returned parts, only takes effect once the
+    /// `LockedPmm` is unlocked.
+    #[inline]
+    pub fn split_locked(
+        self,
+        _pmm: &LockedPmm,
+    ) -> Parts<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7> {
         self.write_regs();
         Parts::new()
     }
@@ -156,12 +156,12 @@ macro_rules! config_fn {
 /// capture trigger event, which determines the input transitions that actually trigger the
 /// capture. By default, all pins use GND as their input source and trigger a capture on a rising
 /// edge.
-pub struct CaptureConfig3<T: CapturePeriph>
+pub struct CaptureConfig3<'a, T: CapturePeriph>
 where
     T: CapCmpTimer3,
 {
     timer: T,
-    config: TimerConfig<T>,
+    config: TimerConfig<'a, T>,
     cap0: PinConfig,
     cap1: PinConfig,
     cap2: PinConfig,
@@ -169,7 +169,7 @@ where
 
 impl<T: CapturePeriph + CapCmpTimer3> CaptureParts3<T> {
     /// Create capture configuration
-    pub fn config(timer: T, config: TimerConfig<T>) -> CaptureConfig3<T> {
+    pub fn config(timer: T, config: TimerConfig<'_, T>) -> CaptureConfig3<'_, T> {
         CaptureConfig3 {
             timer,
             config,
@@ -180,7 +180,7 @@ impl<T: CapturePeriph + CapCmpTimer3> CaptureParts3<T> {
     }
 }
 
-impl<T: CapturePeriph + CapCmpTimer3> CaptureConfig3<T> {
+impl<T: CapturePeriph + CapCmpTimer3> CaptureConfig3<'_, T> {
     config_fn!(
         config_cap0_input_A,
         config_cap0_input_B,
@@ -227,12 +227,12 @@ impl<T: CapturePeriph + CapCmpTimer3> CaptureConfig3<T> {
 /// capture trigger event, which determines the input transitions that actually trigger the
 /// capture. By default, all pins use GND as their input source and trigger a capture on a rising
 /// edge.
-pub struct CaptureConfig7<T: CapturePeriph>
+pub struct CaptureConfig7<'a, T: CapturePeriph>
 where
     T: CapCmpTimer7,
 {
     timer: T,
-    config: TimerConfig<T>,
+    config: TimerConfig<'a, T>,
     cap0: PinConfig,
     cap1: PinConfig,
     cap2: PinConfig,
@@ -244,7 +244,7 @@ where
 
 impl<T: CapturePeriph + CapCmpTimer7> CaptureParts7<T> {
     /// Create capture configuration
-    pub fn config(timer: T, config: TimerConfig<T>) -> CaptureConfig7<T> {
+    pub fn config(timer: T, config: TimerConfig<'_, T>) -> CaptureConfig7<'_, T> {
         CaptureConfig7 {
             timer,
             config,
@@ -259,7 +259,7 @@ impl<T: CapturePeriph + CapCmpTimer7> CaptureParts7<T> {
     }
 }
 
-impl<T: CapturePeriph + CapCmpTimer7> CaptureConfig7<T> {
+impl<T: CapturePeriph + CapCmpTimer7> CaptureConfig7<'_, T> {
     config_fn!(
         config_cap0_input_A,
         config_cap0_input_B,
@@ -1,49 +1,88 @@
 //! Clock system for configuration of MCLK, SMCLK, and ACLK.
 //!
-//! Once configuration is complete, `Aclk` and `Smclk` clock objects are returned. The clock
-//! objects are used to set the clock sources on other peripherals.
+//! Once configuration is complete, `Mclk`, `Aclk`, and `Smclk` clock objects are returned. The
+//! clock objects are used to set the clock sources on other peripherals.
 //! Configuration of MCLK and SMCLK *must* occur, though SMCLK can be disabled. In that case, only
-//! `Aclk` is returned.
+//! `Mclk` and `Aclk` are returned.
 //!
-//! DCO with FLL is supported on MCLK for select frequencies. Supporting arbitrary frequencies on
-//! the DCO requires complex calibration routines not supported by the HAL.
+//! DCO with FLL is supported on MCLK for select frequencies using factory trim, or for arbitrary
+//! frequencies between 1 MHz and 24 MHz using the DCO software trim routine from the user's guide.
+//! The frequencies returned by the clock objects are those actually achieved by the FLL, which may
+//! differ slightly from the requested target.
+//!
+//! The XT1 oscillator can be enabled with either a crystal or an external clock (bypass mode), and
+//! then selected as the source of ACLK, MCLK, or the FLL reference. If XT1 faults, the hardware
+//! automatically switches every clock sourced from XT1 over to REFOCLK. Faults can be monitored
+//! with the `OscFault` object returned by `freeze` when XT1 is enabled.
+//!
+//! Clocks can be reconfigured at runtime by passing everything returned by `freeze` back into
+//! `ClockConfig::reconfigure`, which consumes the old clock objects and returns a fresh builder.
+//!
+//! MCLK, SMCLK, and ACLK can be routed to GPIO pins with `MclkOut`, `SmclkOut`, and `AclkOut`.
+//!
+//! Peripherals can keep SMCLK and ACLK running in low power modes through conditional clock
+//! requests, which are enabled by default. Disabling them on a clock object changes its type, so
+//! peripherals can't silently be clocked from a source that may stop underneath them.
+//!
+//! `ClockTree` computes the same clock frequencies and FRAM wait states with `const fn`s, so they
+//! can be checked at compile time along with derived settings like baud rates. The checked tree is
+//! then applied with `ClockConfig::clock_tree`.
 
 use core::arch::asm;
 
-use crate::delay::Delay;
+use crate::delay::{self, Delay};
 use crate::fram::{Fram, WaitStates};
+use crate::gpio::{Alternate1, Alternate2, Output, Pin, Pin0, Pin1, Pin4, Pin6, Pin7, P1, P2, P3};
+use crate::time::Hertz;
+use core::marker::PhantomData;
 use msp430fr2355 as pac;
 use pac::cs::csctl1::DCORSEL_A;
+use pac::cs::csctl3::SELREF_A;
 use pac::cs::csctl4::{SELA_A, SELMS_A};
 pub use pac::cs::csctl5::{DIVM_A as MclkDiv, DIVS_A as SmclkDiv};
+pub use pac::cs::csctl6::DIVA_A as AclkDiv;
 
 /// REFOCLK frequency
 pub const REFOCLK: u16 = 32768;
 /// VLOCLK frequency
 pub const VLOCLK: u16 = 10000;
+/// XT1CLK frequency in low-frequency mode
+pub const XT1LFCLK: u16 = 32768;
 
+#[derive(Clone, Copy)]
 enum MclkSel {
     Refoclk,
     Vloclk,
     Dcoclk(DcoclkFreqSel),
+    DcoclkTrimmed(DcoTrim),
+    Xt1(u32),
 }
 
 impl MclkSel {
     #[inline]
-    fn freq(&self) -> u32 {
+    const fn freq(&self) -> u32 {
         match self {
             MclkSel::Vloclk => VLOCLK as u32,
             MclkSel::Refoclk => REFOCLK as u32,
             MclkSel::Dcoclk(sel) => sel.freq(),
+            MclkSel::DcoclkTrimmed(trim) => trim.freq(),
+            MclkSel::Xt1(freq) => *freq,
         }
     }
 
+    // MCLK frequency after the MCLK divider is applied
+    #[inline]
+    const fn divided_freq(&self, mclk_div: MclkDiv) -> u32 {
+        self.freq() >> (mclk_div as u32)
+    }
+
     #[inline(always)]
     fn selms(&self) -> SELMS_A {
         match self {
             MclkSel::Vloclk => SELMS_A::VLOCLK,
             MclkSel::Refoclk => SELMS_A::REFOCLK,
-            MclkSel::Dcoclk(_) => SELMS_A::DCOCLKDIV,
+            MclkSel::Dcoclk(_) | MclkSel::DcoclkTrimmed(_) => SELMS_A::DCOCLKDIV,
+            MclkSel::Xt1(_) => SELMS_A::XT1CLK,
         }
     }
 }
@@ -52,6 +91,7 @@ impl MclkSel {
 enum AclkSel {
     Vloclk,
     Refoclk,
+    Xt1(u16),
 }
 
 impl AclkSel {
@@ -60,14 +100,72 @@ impl AclkSel {
         match self {
             AclkSel::Vloclk => SELA_A::VLOCLK,
             AclkSel::Refoclk => SELA_A::REFOCLK,
+            AclkSel::Xt1(_) => SELA_A::XT1CLK,
         }
     }
 
+    // XT1CLK divided by `aclk_div`, which must not exceed the maximum ACLK frequency
+    #[inline]
+    const fn xt1_divided(xt1_freq: u32, aclk_div: AclkDiv) -> Self {
+        let freq = xt1_freq / aclk_div_value(aclk_div);
+        assert!(freq <= ACLK_MAX_FREQ, "ACLK above 40 kHz");
+        AclkSel::Xt1(freq as u16)
+    }
+
     #[inline(always)]
-    fn freq(self) -> u16 {
+    const fn freq(self) -> u16 {
         match self {
             AclkSel::Vloclk => VLOCLK,
             AclkSel::Refoclk => REFOCLK,
+            AclkSel::Xt1(freq) => freq,
+        }
+    }
+}
+
+#[inline(always)]
+const fn aclk_div_value(div: AclkDiv) -> u32 {
+    match div {
+        AclkDiv::_1 => 1,
+        AclkDiv::_16 => 16,
+        AclkDiv::_32 => 32,
+        AclkDiv::_64 => 64,
+        AclkDiv::_128 => 128,
+        AclkDiv::_256 => 256,
+        AclkDiv::_384 => 384,
+        AclkDiv::_512 => 512,
+        AclkDiv::_768 => 768,
+        AclkDiv::_1024 => 1024,
+        AclkDiv::_108 => 108,
+        AclkDiv::_338 => 338,
+        AclkDiv::_414 => 414,
+        AclkDiv::_640 => 640,
+        // Reserved settings, which behave as no division
+        AclkDiv::DIVA_14 | AclkDiv::DIVA_15 => 1,
+    }
+}
+
+/// XT1 oscillator drive strength. Higher drive strengths start up faster and support larger load
+/// capacitances, at the cost of higher current consumption.
+#[derive(Clone, Copy)]
+pub enum Xt1Drive {
+    /// Lowest drive strength and current consumption
+    Lowest,
+    /// Increased drive strength
+    Low,
+    /// Further increased drive strength
+    High,
+    /// Maximum drive strength and current consumption
+    Highest,
+}
+
+impl Xt1Drive {
+    #[inline(always)]
+    fn xt1drive(self) -> u8 {
+        match self {
+            Xt1Drive::Lowest => 0,
+            Xt1Drive::Low => 1,
+            Xt1Drive::High => 2,
+            Xt1Drive::Highest => 3,
         }
     }
 }
@@ -110,7 +208,7 @@ impl DcoclkFreqSel {
     }
 
     #[inline(always)]
-    fn multiplier(self) -> u16 {
+    const fn multiplier(self) -> u16 {
         match self {
             DcoclkFreqSel::_1MHz => 32,
             DcoclkFreqSel::_2MHz => 61,
@@ -125,20 +223,234 @@ impl DcoclkFreqSel {
 
     /// Numerical frequency
     #[inline]
-    pub fn freq(self) -> u32 {
+    pub const fn freq(self) -> u32 {
         (self.multiplier() as u32) * (REFOCLK as u32)
     }
 }
 
+/// Lowest DCO frequency that can be targeted with software trim
+const DCO_MIN_FREQ: u32 = 1_000_000;
+/// Highest DCO frequency that can be targeted with software trim
+const DCO_MAX_FREQ: u32 = 24_000_000;
+
+/// FLL settings for an arbitrary DCO frequency, tuned at runtime with software trim
+#[derive(Clone, Copy)]
+struct DcoTrim {
+    // FLL multiplier relative to REFOCLK, equal to FLLN + 1
+    multiplier: u16,
+}
+
+impl DcoTrim {
+    #[inline]
+    const fn new(target_freq: u32) -> Self {
+        assert!(target_freq >= DCO_MIN_FREQ, "DCO target below 1 MHz");
+        assert!(target_freq <= DCO_MAX_FREQ, "DCO target above 24 MHz");
+        let refoclk = REFOCLK as u32;
+        // Round to the nearest multiple of the FLL reference
+        let multiplier = ((target_freq + refoclk / 2) / refoclk) as u16;
+        DcoTrim { multiplier }
+    }
+
+    // Select the DCO range whose nominal frequency is closest to the target
+    #[inline]
+    fn dcorsel(self) -> DCORSEL_A {
+        match self.freq() {
+            0..=1_499_999 => DCORSEL_A::DCORSEL_0,
+            1_500_000..=2_999_999 => DCORSEL_A::DCORSEL_1,
+            3_000_000..=5_999_999 => DCORSEL_A::DCORSEL_2,
+            6_000_000..=9_999_999 => DCORSEL_A::DCORSEL_3,
+            10_000_000..=13_999_999 => DCORSEL_A::DCORSEL_4,
+            14_000_000..=17_999_999 => DCORSEL_A::DCORSEL_5,
+            18_000_000..=21_999_999 => DCORSEL_A::DCORSEL_6,
+            _ => DCORSEL_A::DCORSEL_7,
+        }
+    }
+
+    #[inline]
+    const fn freq(self) -> u32 {
+        (self.multiplier as u32) * (REFOCLK as u32)
+    }
+}
+
 /// Typestate for `ClockConfig` that represents unconfigured clocks
 pub struct NoClockDefined;
 /// Typestate for `ClockConfig` that represents a configured MCLK
 pub struct MclkDefined(MclkSel);
-/// Typestate for `ClockConfig` that represents a configured SMCLK
+/// Typestate for `ClockConfig` and `ClockTree` that represents a configured SMCLK
+#[derive(Clone, Copy)]
 pub struct SmclkDefined(SmclkDiv);
-/// Typestate for `ClockConfig` that represents disabled SMCLK
+/// Typestate for `ClockConfig` and `ClockTree` that represents disabled SMCLK
+#[derive(Clone, Copy)]
 pub struct SmclkDisabled;
 
+/// Typestate for `ClockConfig` that represents a disabled XT1 oscillator
+pub struct Xt1Off;
+/// Typestate for `ClockConfig` that represents XT1 running at 32768 Hz in low-frequency mode
+pub struct Xt1Lf {
+    drive: Xt1Drive,
+    bypass: bool,
+}
+/// Typestate for `ClockConfig` that represents XT1 running in high-frequency mode
+pub struct Xt1Hf {
+    freq: u32,
+    drive: Xt1Drive,
+    bypass: bool,
+    aclk_div: AclkDiv,
+}
+
+// Hidden for the same reason as SmclkState
+#[doc(hidden)]
+pub trait Xt1State {
+    // Start XT1 while MCLK runs at `mclk_freq`
+    fn configure(&self, periph: &pac::CS, mclk_freq: u32);
+
+    // XT1 frequency, if enabled
+    fn freq(&self) -> Option<u32>;
+
+    // Divide XT1CLK by `aclk_div` for ACLK
+    fn set_aclk_div(&mut self, aclk_div: AclkDiv);
+}
+
+// Hidden for the same reason as SmclkState
+#[doc(hidden)]
+pub trait Xt1Enabled: Xt1State {}
+
+impl Xt1State for Xt1Off {
+    #[inline(always)]
+    fn configure(&self, _periph: &pac::CS, _mclk_freq: u32) {}
+
+    #[inline(always)]
+    fn freq(&self) -> Option<u32> {
+        None
+    }
+
+    #[inline(always)]
+    fn set_aclk_div(&mut self, _aclk_div: AclkDiv) {}
+}
+
+impl Xt1State for Xt1Lf {
+    #[inline]
+    fn configure(&self, periph: &pac::CS, mclk_freq: u32) {
+        start_xt1(
+            periph,
+            mclk_freq,
+            false,
+            0,
+            self.drive,
+            self.bypass,
+            AclkDiv::_1,
+        );
+    }
+
+    #[inline(always)]
+    fn freq(&self) -> Option<u32> {
+        Some(XT1LFCLK as u32)
+    }
+
+    #[inline]
+    fn set_aclk_div(&mut self, aclk_div: AclkDiv) {
+        // The ACLK divider only applies to XT1 in high-frequency mode
+        assert!(aclk_div == AclkDiv::_1, "XT1 in LF mode can't divide ACLK");
+    }
+}
+impl Xt1Enabled for Xt1Lf {}
+
+impl Xt1State for Xt1Hf {
+    #[inline]
+    fn configure(&self, periph: &pac::CS, mclk_freq: u32) {
+        // XT1HFFREQ bands from the user's guide
+        let hffreq = match self.freq {
+            0..=4_000_000 => 0,
+            4_000_001..=6_000_000 => 1,
+            6_000_001..=16_000_000 => 2,
+            _ => 3,
+        };
+        start_xt1(
+            periph,
+            mclk_freq,
+            true,
+            hffreq,
+            self.drive,
+            self.bypass,
+            self.aclk_div,
+        );
+    }
+
+    #[inline(always)]
+    fn freq(&self) -> Option<u32> {
+        Some(self.freq)
+    }
+
+    #[inline(always)]
+    fn set_aclk_div(&mut self, aclk_div: AclkDiv) {
+        self.aclk_div = aclk_div;
+    }
+}
+impl Xt1Enabled for Xt1Hf {}
+
+// Longest time to wait for XT1 to stabilize, which is about twice the typical start-up time of a
+// 32768 Hz crystal
+const XT1_START_TIMEOUT_MS: u32 = 2000;
+
+// Start XT1 and wait for it to stabilize, unless it's already running with the same settings. If
+// XT1 doesn't stabilize in time, its fault flag is left set and the clocks sourced from XT1 stay on
+// REFOCLK.
+#[inline]
+fn start_xt1(
+    periph: &pac::CS,
+    mclk_freq: u32,
+    hf: bool,
+    hffreq: u8,
+    drive: Xt1Drive,
+    bypass: bool,
+    aclk_div: AclkDiv,
+) {
+    let csctl6 = periph.csctl6.read();
+    // XT1AUTOOFF is only cleared once XT1 has been explicitly enabled
+    let running = csctl6.xt1autooff().is_xt1autooff_0()
+        && csctl6.xts().bit() == hf
+        && csctl6.xt1bypass().bit() == bypass
+        && csctl6.xt1hffreq().bits() == hffreq
+        && csctl6.xt1drive().bits() == drive.xt1drive();
+    if running {
+        periph.csctl6.modify(|_, w| w.diva().variant(aclk_div));
+        return;
+    }
+
+    periph.csctl6.write(|w| {
+        // Keep XT1 on even if nothing requests it, since it was explicitly enabled
+        w.xt1autooff()
+            .xt1autooff_0()
+            .xt1faultoff()
+            .xt1faultoff_0()
+            .xts()
+            .bit(hf)
+            .xt1bypass()
+            .bit(bypass)
+            .xt1hffreq()
+            .bits(hffreq)
+            .xt1drive()
+            .bits(drive.xt1drive())
+            .diva()
+            .variant(aclk_div)
+    });
+
+    // Clear the fault flags until they stay cleared, which means XT1 has stabilized
+    let sfr = unsafe { &*pac::SFR::ptr() };
+    for _ in 0..XT1_START_TIMEOUT_MS {
+        unsafe {
+            periph
+                .csctl7
+                .clear_bits(|w| w.xt1offg().clear_bit().dcoffg().clear_bit())
+        };
+        unsafe { sfr.sfrifg1.clear_bits(|w| w.ofifg().clear_bit()) };
+        if sfr.sfrifg1.read().ofifg().bit_is_clear() {
+            break;
+        }
+        delay::delay_cycles(mclk_freq / 1000);
+    }
+}
+
 // Using SmclkState as a trait bound outside the HAL will never be useful, since we only configure
 // the clock once, so just keep it hidden
 #[doc(hidden)]
@@ -163,23 +475,41 @@ impl SmclkState for SmclkDisabled {
 /// Builder object that configures system clocks
 ///
 /// Can only commit configurations to hardware if both MCLK and SMCLK settings have been
-/// configured. ACLK configurations are optional, with its default source being REFOCLK.
-pub struct ClockConfig<MCLK, SMCLK> {
+/// configured. ACLK configurations are optional, with its default source being REFOCLK. XT1 is
+/// disabled unless explicitly enabled, and can only be selected as a clock source once enabled.
+pub struct ClockConfig<MCLK, SMCLK, XT1 = Xt1Off> {
     periph: pac::CS,
     mclk: MCLK,
     mclk_div: MclkDiv,
     aclk_sel: AclkSel,
+    fll_ref: SELREF_A,
     smclk: SMCLK,
+    xt1: XT1,
+    mclk_req: bool,
+    refo_lp: bool,
+    vlo_auto_off: bool,
+    // MCLK frequency before the configuration is applied
+    old_mclk_freq: u32,
 }
 
 macro_rules! make_clkconf {
     ($conf:expr, $mclk:expr, $smclk:expr) => {
+        make_clkconf!($conf, $mclk, $smclk, $conf.xt1)
+    };
+
+    ($conf:expr, $mclk:expr, $smclk:expr, $xt1:expr) => {
         ClockConfig {
             periph: $conf.periph,
             mclk: $mclk,
             mclk_div: $conf.mclk_div,
             aclk_sel: $conf.aclk_sel,
+            fll_ref: $conf.fll_ref,
             smclk: $smclk,
+            xt1: $xt1,
+            mclk_req: $conf.mclk_req,
+            refo_lp: $conf.refo_lp,
+            vlo_auto_off: $conf.vlo_auto_off,
+            old_mclk_freq: $conf.old_mclk_freq,
         }
     };
 }
@@ -193,11 +523,130 @@ impl ClockConfig<NoClockDefined, NoClockDefined> {
             mclk: NoClockDefined,
             mclk_div: MclkDiv::_1,
             aclk_sel: AclkSel::Refoclk,
+            fll_ref: SELREF_A::REFOCLK,
+            xt1: Xt1Off,
+            mclk_req: true,
+            refo_lp: false,
+            vlo_auto_off: true,
+            old_mclk_freq: RESET_MCLK_FREQ,
+        }
+    }
+}
+
+impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK, Xt1Off> {
+    /// Enable XT1 with a 32768 Hz watch crystal connected to XIN and XOUT
+    #[inline]
+    pub fn xt1_crystal_lf<DIRI, DIRO>(
+        self,
+        _xin: Pin<P2, Pin7, Alternate2<DIRI>>,
+        _xout: Pin<P2, Pin6, Alternate2<DIRO>>,
+        drive: Xt1Drive,
+    ) -> ClockConfig<MCLK, SMCLK, Xt1Lf> {
+        let xt1 = Xt1Lf {
+            drive,
+            bypass: false,
+        };
+        make_clkconf!(self, self.mclk, self.smclk, xt1)
+    }
+
+    /// Enable XT1 with a high-frequency crystal of `freq` Hz (up to 24 MHz) connected to XIN and
+    /// XOUT
+    #[inline]
+    pub fn xt1_crystal_hf<DIRI, DIRO>(
+        self,
+        _xin: Pin<P2, Pin7, Alternate2<DIRI>>,
+        _xout: Pin<P2, Pin6, Alternate2<DIRO>>,
+        freq: Hertz,
+        drive: Xt1Drive,
+    ) -> ClockConfig<MCLK, SMCLK, Xt1Hf> {
+        let xt1 = Xt1Hf {
+            freq: freq.raw(),
+            drive,
+            bypass: false,
+            aclk_div: AclkDiv::_1,
+        };
+        make_clkconf!(self, self.mclk, self.smclk, xt1)
+    }
+
+    /// Enable XT1 in bypass mode, using a 32768 Hz external clock signal connected to XIN
+    #[inline]
+    pub fn xt1_bypass_lf<DIR>(
+        self,
+        _xin: Pin<P2, Pin7, Alternate2<DIR>>,
+    ) -> ClockConfig<MCLK, SMCLK, Xt1Lf> {
+        let xt1 = Xt1Lf {
+            drive: Xt1Drive::Lowest,
+            bypass: true,
+        };
+        make_clkconf!(self, self.mclk, self.smclk, xt1)
+    }
+
+    /// Enable XT1 in bypass mode, using a high-frequency external clock signal of `freq` Hz (up to
+    /// 24 MHz) connected to XIN
+    #[inline]
+    pub fn xt1_bypass_hf<DIR>(
+        self,
+        _xin: Pin<P2, Pin7, Alternate2<DIR>>,
+        freq: Hertz,
+    ) -> ClockConfig<MCLK, SMCLK, Xt1Hf> {
+        let xt1 = Xt1Hf {
+            freq: freq.raw(),
+            drive: Xt1Drive::Lowest,
+            bypass: true,
+            aclk_div: AclkDiv::_1,
+        };
+        make_clkconf!(self, self.mclk, self.smclk, xt1)
+    }
+}
+
+impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK, Xt1Lf> {
+    /// Select XT1CLK for ACLK. Frequency is 32768 Hz.
+    #[inline]
+    pub fn aclk_xt1(mut self) -> Self {
+        self.aclk_sel = AclkSel::Xt1(XT1LFCLK);
+        self
+    }
+
+    /// Select XT1CLK for MCLK and set the MCLK divider. Frequency is `32768 / mclk_div` Hz.
+    #[inline]
+    pub fn mclk_xt1(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK, Xt1Lf> {
+        ClockConfig {
+            mclk_div,
+            ..make_clkconf!(self, MclkDefined(MclkSel::Xt1(XT1LFCLK as u32)), self.smclk)
+        }
+    }
+
+    /// Use XT1CLK instead of REFOCLK as the FLL reference when MCLK is sourced from DCOCLK, which
+    /// improves the accuracy of the DCO.
+    #[inline]
+    pub fn fll_ref_xt1(mut self) -> Self {
+        self.fll_ref = SELREF_A::XT1CLK;
+        self
+    }
+}
+
+impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK, Xt1Hf> {
+    /// Select XT1CLK for ACLK, divided by `aclk_div`. Frequency is `xt1_freq / aclk_div` Hz.
+    /// Panics if the resulting ACLK frequency exceeds 40 kHz.
+    #[inline]
+    pub fn aclk_xt1(mut self, aclk_div: AclkDiv) -> Self {
+        self.xt1.aclk_div = aclk_div;
+        self.aclk_sel = AclkSel::xt1_divided(self.xt1.freq, aclk_div);
+        self
+    }
+
+    /// Select XT1CLK for MCLK and set the MCLK divider. Frequency is `xt1_freq / mclk_div` Hz.
+    #[inline]
+    pub fn mclk_xt1(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK, Xt1Hf> {
+        let freq = self.xt1.freq;
+        ClockConfig {
+            mclk_div,
+            ..make_clkconf!(self, MclkDefined(MclkSel::Xt1(freq)), self.smclk)
         }
     }
 }
 
-impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
+impl<MCLK, SMCLK, XT1> ClockConfig<MCLK, SMCLK, XT1> {
     /// Select REFOCLK for ACLK
     #[inline]
     pub fn aclk_refoclk(mut self) -> Self {
@@ -214,7 +663,7 @@ impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
 
     /// Select REFOCLK for MCLK and set the MCLK divider. Frequency is `10000 / mclk_div` Hz.
     #[inline]
-    pub fn mclk_refoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK> {
+    pub fn mclk_refoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK, XT1> {
         ClockConfig {
             mclk_div,
             ..make_clkconf!(self, MclkDefined(MclkSel::Refoclk), self.smclk)
@@ -223,7 +672,7 @@ impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
 
     /// Select VLOCLK for MCLK and set the MCLK divider. Frequency is `32768 / mclk_div` Hz.
     #[inline]
-    pub fn mclk_vcoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK> {
+    pub fn mclk_vcoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK, XT1> {
         ClockConfig {
             mclk_div,
             ..make_clkconf!(self, MclkDefined(MclkSel::Vloclk), self.smclk)
@@ -238,24 +687,87 @@ impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
         self,
         target_freq: DcoclkFreqSel,
         mclk_div: MclkDiv,
-    ) -> ClockConfig<MclkDefined, SMCLK> {
+    ) -> ClockConfig<MclkDefined, SMCLK, XT1> {
         ClockConfig {
             mclk_div,
             ..make_clkconf!(self, MclkDefined(MclkSel::Dcoclk(target_freq)), self.smclk)
         }
     }
 
+    /// Select DCOCLK for MCLK with FLL for stabilization, targeting an arbitrary frequency. The
+    /// target is rounded to the nearest multiple of REFOCLK, so the achieved frequency is
+    /// `round(target / 32768) * 32768 / mclk_div` Hz. Panics if the target is outside of the 1 MHz
+    /// to 24 MHz DCO range. During `freeze`, the DCO range is selected automatically and the DCO
+    /// trim is calibrated using the software trim routine from the user's guide.
+    #[inline]
+    pub fn mclk_dco_hz(
+        self,
+        target: Hertz,
+        mclk_div: MclkDiv,
+    ) -> ClockConfig<MclkDefined, SMCLK, XT1> {
+        ClockConfig {
+            mclk_div,
+            ..make_clkconf!(
+                self,
+                MclkDefined(MclkSel::DcoclkTrimmed(DcoTrim::new(target.raw()))),
+                self.smclk
+            )
+        }
+    }
+
     /// Enable SMCLK and set SMCLK divider, which divides the MCLK frequency
     #[inline]
-    pub fn smclk_on(self, div: SmclkDiv) -> ClockConfig<MCLK, SmclkDefined> {
+    pub fn smclk_on(self, div: SmclkDiv) -> ClockConfig<MCLK, SmclkDefined, XT1> {
         make_clkconf!(self, self.mclk, SmclkDefined(div))
     }
 
     /// Disable SMCLK
     #[inline]
-    pub fn smclk_off(self) -> ClockConfig<MCLK, SmclkDisabled> {
+    pub fn smclk_off(self) -> ClockConfig<MCLK, SmclkDisabled, XT1> {
         make_clkconf!(self, self.mclk, SmclkDisabled)
     }
+
+    /// Ignore conditional requests for MCLK from peripherals such as DMA, so MCLK only runs when
+    /// the CPU is active. MCLK requests are honoured by default.
+    #[inline]
+    pub fn mclk_requests_off(mut self) -> Self {
+        self.mclk_req = false;
+        self
+    }
+
+    /// Run REFO in low-power mode, which reduces its current consumption at the cost of a less
+    /// accurate REFOCLK. Applies to every use of REFOCLK, including the FLL reference.
+    #[inline]
+    pub fn refo_low_power(mut self) -> Self {
+        self.refo_lp = true;
+        self
+    }
+
+    /// Keep VLO running even when no clock is sourced from it. By default VLO is turned off
+    /// automatically when unused.
+    #[inline]
+    pub fn vlo_always_on(mut self) -> Self {
+        self.vlo_auto_off = false;
+        self
+    }
+}
+
+// SMCLK frequency derived from MCLK
+#[inline]
+const fn smclk_freq(mclk_freq: u32, smclk_div: SmclkDiv) -> u32 {
+    mclk_freq >> (smclk_div as u32)
+}
+
+// FRAM wait states required for an MCLK frequency
+#[inline]
+const fn wait_states(mclk_freq: u32) -> WaitStates {
+    if mclk_freq > 16_000_000 {
+        WaitStates::Wait2
+    } else if mclk_freq > 8_000_000 {
+        WaitStates::Wait1
+    } else {
+        WaitStates::Wait0
+    }
 }
 
 #[inline(always)]
@@ -270,7 +782,7 @@ fn fll_on() {
     unsafe { asm!("bic.b 64, SR", options(nomem, nostack)) };
 }
 
-impl<SMCLK: SmclkState> ClockConfig<MclkDefined, SMCLK> {
+impl<SMCLK: SmclkState, XT1: Xt1State> ClockConfig<MclkDefined, SMCLK, XT1> {
     #[inline]
     fn configure_dco_fll(&self) {
         // Run FLL configuration procedure from the user's guide if we are using DCO
@@ -283,7 +795,9 @@ impl<SMCLK: SmclkState> ClockConfig<MclkDefined, SMCLK> {
             msp430::asm::nop();
             msp430::asm::nop();
 
-            self.periph.csctl3.write(|w| w.selref().refoclk());
+            self.periph
+                .csctl3
+                .write(|w| w.selref().variant(self.fll_ref));
             self.periph.csctl0.write(|w| unsafe { w.bits(0) });
             self.periph
                 .csctl1
@@ -304,7 +818,116 @@ impl<SMCLK: SmclkState> ClockConfig<MclkDefined, SMCLK> {
             fll_on();
 
             while !self.periph.csctl7.read().fllunlock().is_fllunlock_0() {}
+        } else if let MclkSel::DcoclkTrimmed(trim) = self.mclk.0 {
+            fll_off();
+            msp430::asm::nop();
+            msp430::asm::nop();
+            msp430::asm::nop();
+
+            self.periph
+                .csctl3
+                .write(|w| w.selref().variant(self.fll_ref));
+            self.periph.csctl0.write(|w| unsafe { w.bits(0) });
+            // Start the search from the middle of the trim range
+            self.periph.csctl1.write(|w| {
+                unsafe { w.dcoftrim().bits(3) }
+                    .dcoftrimen()
+                    .dcoftrimen_1()
+                    .dcorsel()
+                    .variant(trim.dcorsel())
+            });
+            self.periph
+                .csctl2
+                .write(|w| unsafe { w.flln().bits(trim.multiplier - 1) }.flld()._1());
+
+            msp430::asm::nop();
+            msp430::asm::nop();
+            msp430::asm::nop();
+            fll_on();
+
+            self.dco_software_trim(trim.freq());
+        }
+    }
+
+    // Software trim routine from the user's guide. Steps DCOFTRIM until the DCO tap locked by the
+    // FLL crosses the middle of its range (256), then loads the trim and tap closest to the middle,
+    // which gives the FLL the most headroom to track temperature and voltage drift.
+    #[inline]
+    fn dco_software_trim(&self, dco_freq: u32) {
+        const TAP_MID: u16 = 256;
+        const TRIM_MAX: u8 = 7;
+
+        let mut old_tap: Option<u16> = None;
+        let mut best_delta = u16::MAX;
+        let mut best_csctl0 = 0;
+        let mut best_csctl1 = 0;
+
+        loop {
+            self.periph
+                .csctl0
+                .write(|w| unsafe { w.dco().bits(TAP_MID) });
+            // Clear DCO fault flag until it stays cleared
+            loop {
+                unsafe { self.periph.csctl7.clear_bits(|w| w.dcoffg().clear_bit()) };
+                if self.periph.csctl7.read().dcoffg().bit_is_clear() {
+                    break;
+                }
+            }
+
+            // The FLL lock status is only stable after around 24 cycles of the FLL reference,
+            // which is roughly 3000 DCO cycles per MHz. Each loop iteration is at least 3 cycles.
+            for _ in 0..(dco_freq / 1000) {
+                msp430::asm::nop();
+            }
+            // Wait for the FLL to lock, unless the DCO has faulted
+            while {
+                let csctl7 = self.periph.csctl7.read();
+                !csctl7.fllunlock().is_fllunlock_0() && csctl7.dcoffg().bit_is_clear()
+            } {}
+
+            let csctl0 = self.periph.csctl0.read();
+            let csctl1 = self.periph.csctl1.read();
+            let new_tap = csctl0.dco().bits();
+            let dcoftrim = csctl1.dcoftrim().bits();
+
+            let (delta, crossed, next_trim) = if new_tap < TAP_MID {
+                // DCO is too fast at this trim setting, so step trim down
+                (
+                    TAP_MID - new_tap,
+                    matches!(old_tap, Some(tap) if tap >= TAP_MID),
+                    dcoftrim.checked_sub(1),
+                )
+            } else {
+                // DCO is too slow at this trim setting, so step trim up
+                (
+                    new_tap - TAP_MID,
+                    matches!(old_tap, Some(tap) if tap < TAP_MID),
+                    Some(dcoftrim + 1).filter(|&t| t <= TRIM_MAX),
+                )
+            };
+
+            if delta < best_delta {
+                best_csctl0 = csctl0.bits();
+                best_csctl1 = csctl1.bits();
+                best_delta = delta;
+            }
+
+            match next_trim {
+                Some(trim) if !crossed => {
+                    self.periph
+                        .csctl1
+                        .modify(|r, w| unsafe { w.bits(r.bits()).dcoftrim().bits(trim) });
+                }
+                // Stop once the tap has crossed the middle or we've run out of trim range
+                _ => break,
+            }
+            old_tap = Some(new_tap);
         }
+
+        // Reload the tap and trim closest to the middle of the range
+        self.periph.csctl0.write(|w| unsafe { w.bits(best_csctl0) });
+        self.periph.csctl1.write(|w| unsafe { w.bits(best_csctl1) });
+        while !self.periph.csctl7.read().fllunlock().is_fllunlock_0() {}
     }
 
     #[inline]
@@ -318,60 +941,552 @@ impl<SMCLK: SmclkState> ClockConfig<MclkDefined, SMCLK> {
         });
 
         self.periph.csctl5.write(|w| {
-            let w = w.vloautooff().set_bit().divm().variant(self.mclk_div);
+            let w = w
+                .vloautooff()
+                .bit(self.vlo_auto_off)
+                .divm()
+                .variant(self.mclk_div);
             match self.smclk.div() {
                 Some(div) => w.divs().variant(div),
                 None => w.smclkoff().set_bit(),
             }
         });
+
+        self.periph
+            .csctl3
+            .modify(|r, w| unsafe { w.bits(r.bits()) }.refolp().bit(self.refo_lp));
+
+        // Clock objects returned by freeze always start with conditional requests enabled
+        self.periph.csctl8.modify(|r, w| {
+            unsafe { w.bits(r.bits()) }
+                .aclkreqen()
+                .set_bit()
+                .mclkreqen()
+                .bit(self.mclk_req)
+                .smclkreqen()
+                .set_bit()
+        });
     }
 
+    // MCLK frequency while the DCO is retuned, before the new MCLK source and divider are
+    // selected. If MCLK is currently sourced from DCOCLKDIV, it runs at the new DCO frequency
+    // divided by the old MCLK divider in the meantime.
     #[inline]
-    unsafe fn configure_fram(fram: &mut Fram, mclk_freq: u32) {
-        if mclk_freq > 16_000_000 {
-            fram.set_wait_states(WaitStates::Wait2);
-        } else if mclk_freq > 8_000_000 {
-            fram.set_wait_states(WaitStates::Wait1);
+    fn transient_mclk_freq(&self) -> u32 {
+        let retunes_dco = matches!(self.mclk.0, MclkSel::Dcoclk(_) | MclkSel::DcoclkTrimmed(_));
+        if retunes_dco && self.periph.csctl4.read().selms().is_dcoclkdiv() {
+            self.mclk.0.freq() >> self.periph.csctl5.read().divm().bits()
         } else {
-            fram.set_wait_states(WaitStates::Wait0);
+            0
         }
     }
-}
 
-impl ClockConfig<MclkDefined, SmclkDefined> {
-    /// Apply clock configuration to hardware and return SMCLK and ACLK clock objects
-    /// Also returns delay provider
+    // Apply the configuration to hardware and return the MCLK frequency
     #[inline]
-    pub fn freeze(self, fram: &mut Fram) -> (Smclk, Aclk, Delay) {
-        let mclk_freq = self.mclk.0.freq() >> (self.mclk_div as u32);
-        unsafe { Self::configure_fram(fram, mclk_freq) };
+    fn apply(&self, fram: &mut Fram) -> u32 {
+        let mclk_freq = self.mclk.0.divided_freq(self.mclk_div);
+        let wait = wait_states(mclk_freq);
+        // FRAM must never be accessed with fewer wait states than MCLK requires. The current wait
+        // states already cover the old MCLK, so they are raised to also cover the transient and
+        // new MCLK before anything changes, and lowered to the final setting only afterwards.
+        let transient_wait = wait_states(self.transient_mclk_freq());
+        let peak = if transient_wait as u8 > wait as u8 {
+            transient_wait
+        } else {
+            wait
+        };
+        if peak as u8 > fram.wait_states() {
+            unsafe { fram.set_wait_states(peak) };
+        }
+        self.xt1.configure(&self.periph, self.old_mclk_freq);
         self.configure_dco_fll();
         self.configure_cs();
+        if wait as u8 != fram.wait_states() {
+            unsafe { fram.set_wait_states(wait) };
+        }
+        mclk_freq
+    }
+}
+
+impl ClockConfig<MclkDefined, SmclkDefined> {
+    /// Apply clock configuration to hardware and return MCLK, SMCLK, and ACLK clock objects
+    /// Also returns delay provider. Clock objects and delay provider use the frequencies actually
+    /// achieved by the clock system.
+    #[inline]
+    pub fn freeze(self, fram: &mut Fram) -> (Mclk, Smclk, Aclk, Delay) {
+        let mclk_freq = self.apply(fram);
         (
-            Smclk(mclk_freq >> (self.smclk.0 as u32)),
-            Aclk(self.aclk_sel.freq()),
-            Delay::new(mclk_freq),
+            Mclk::new(mclk_freq, self.periph),
+            Smclk::new(smclk_freq(mclk_freq, self.smclk.0)),
+            Aclk::new(self.aclk_sel.freq() as u32),
+            Delay::new(Hertz::from_raw(mclk_freq)),
         )
     }
 }
 
 impl ClockConfig<MclkDefined, SmclkDisabled> {
-    /// Apply clock configuration to hardware and return ACLK clock object, as SMCLK is disabled
-    /// Also returns delay provider.
+    /// Apply clock configuration to hardware and return MCLK and ACLK clock objects, as SMCLK is
+    /// disabled. Also returns delay provider. Clock objects and delay provider use the frequencies
+    /// actually achieved by the clock system.
     #[inline]
-    pub fn freeze(self, fram: &mut Fram) -> (Aclk, Delay) {
-        let mclk_freq = self.mclk.0.freq() >> (self.mclk_div as u32);
-        self.configure_dco_fll();
-        unsafe { Self::configure_fram(fram, mclk_freq) };
-        self.configure_cs();
-        (Aclk(self.aclk_sel.freq()), Delay::new(mclk_freq))
+    pub fn freeze(self, fram: &mut Fram) -> (Mclk<SmclkDisabled>, Aclk, Delay) {
+        let mclk_freq = self.apply(fram);
+        (
+            Mclk::new(mclk_freq, self.periph),
+            Aclk::new(self.aclk_sel.freq() as u32),
+            Delay::new(Hertz::from_raw(mclk_freq)),
+        )
+    }
+}
+
+impl<XT1: Xt1Enabled> ClockConfig<MclkDefined, SmclkDefined, XT1> {
+    /// Apply clock configuration to hardware and return MCLK, SMCLK, and ACLK clock objects, the
+    /// delay provider, and the oscillator fault monitor. Waits up to two seconds for XT1 to
+    /// stabilize, unless it's already running with the same settings. If it doesn't, the clocks
+    /// sourced from XT1 run from REFOCLK and `OscFault::xt1_status` reports `FallbackToRefo`.
+    #[inline]
+    pub fn freeze(self, fram: &mut Fram) -> (Mclk, Smclk, Aclk, Delay, OscFault<XT1>) {
+        let mclk_freq = self.apply(fram);
+        (
+            Mclk::new(mclk_freq, self.periph),
+            Smclk::new(smclk_freq(mclk_freq, self.smclk.0)),
+            Aclk::new(self.aclk_sel.freq() as u32),
+            Delay::new(Hertz::from_raw(mclk_freq)),
+            OscFault(self.xt1),
+        )
+    }
+}
+
+impl<XT1: Xt1Enabled> ClockConfig<MclkDefined, SmclkDisabled, XT1> {
+    /// Apply clock configuration to hardware and return MCLK and ACLK clock objects, as SMCLK is
+    /// disabled. Also returns the delay provider and the oscillator fault monitor. Waits up to two
+    /// seconds for XT1 to stabilize, unless it's already running with the same settings. If it
+    /// doesn't, the clocks sourced from XT1 run from REFOCLK and `OscFault::xt1_status` reports
+    /// `FallbackToRefo`.
+    #[inline]
+    pub fn freeze(self, fram: &mut Fram) -> (Mclk<SmclkDisabled>, Aclk, Delay, OscFault<XT1>) {
+        let mclk_freq = self.apply(fram);
+        (
+            Mclk::new(mclk_freq, self.periph),
+            Aclk::new(self.aclk_sel.freq() as u32),
+            Delay::new(Hertz::from_raw(mclk_freq)),
+            OscFault(self.xt1),
+        )
+    }
+}
+
+// Using FrozenClocks as a trait bound outside the HAL isn't useful, since it's only implemented for
+// the outputs of `freeze`, so just keep it hidden
+#[doc(hidden)]
+pub trait FrozenClocks {
+    type Xt1;
+
+    fn into_parts(self) -> (pac::CS, u32, Self::Xt1);
+}
+
+impl<SREQ, AREQ> FrozenClocks for (Mclk, Smclk<SREQ>, Aclk<AREQ>, Delay) {
+    type Xt1 = Xt1Off;
+
+    #[inline(always)]
+    fn into_parts(self) -> (pac::CS, u32, Xt1Off) {
+        ((self.0).1, (self.0).0.raw(), Xt1Off)
+    }
+}
+
+impl<AREQ> FrozenClocks for (Mclk<SmclkDisabled>, Aclk<AREQ>, Delay) {
+    type Xt1 = Xt1Off;
+
+    #[inline(always)]
+    fn into_parts(self) -> (pac::CS, u32, Xt1Off) {
+        ((self.0).1, (self.0).0.raw(), Xt1Off)
+    }
+}
+
+impl<SREQ, AREQ, XT1: Xt1Enabled> FrozenClocks
+    for (Mclk, Smclk<SREQ>, Aclk<AREQ>, Delay, OscFault<XT1>)
+{
+    type Xt1 = XT1;
+
+    #[inline(always)]
+    fn into_parts(self) -> (pac::CS, u32, XT1) {
+        ((self.0).1, (self.0).0.raw(), (self.4).0)
+    }
+}
+
+impl<AREQ, XT1: Xt1Enabled> FrozenClocks
+    for (Mclk<SmclkDisabled>, Aclk<AREQ>, Delay, OscFault<XT1>)
+{
+    type Xt1 = XT1;
+
+    #[inline(always)]
+    fn into_parts(self) -> (pac::CS, u32, XT1) {
+        ((self.0).1, (self.0).0.raw(), (self.3).0)
+    }
+}
+
+impl<XT1> ClockConfig<NoClockDefined, NoClockDefined, XT1> {
+    /// Recover a fresh clock builder from the objects returned by a previous `freeze`, so the
+    /// clocks can be reconfigured at runtime. The old clock objects and delay provider are
+    /// consumed, so they can no longer be used to configure peripherals with stale frequencies.
+    /// Peripherals configured from a clock object borrow it, so they have to be dropped or freed
+    /// before the clocks can be reconfigured, and set up again with the new clock objects returned
+    /// by the next `freeze`.
+    ///
+    /// If XT1 was enabled, it stays enabled with the same settings and isn't restarted by the next
+    /// `freeze`. All other settings, including the ACLK source, start from their defaults. The
+    /// clocks keep running with the old configuration until `freeze` is called, which also adjusts
+    /// the FRAM wait states in the correct order relative to the MCLK frequency change.
+    #[inline]
+    pub fn reconfigure<F: FrozenClocks<Xt1 = XT1>>(frozen: F) -> Self {
+        let (cs, mclk_freq, xt1) = frozen.into_parts();
+        ClockConfig {
+            periph: cs,
+            smclk: NoClockDefined,
+            mclk: NoClockDefined,
+            mclk_div: MclkDiv::_1,
+            aclk_sel: AclkSel::Refoclk,
+            fll_ref: SELREF_A::REFOCLK,
+            xt1,
+            mclk_req: true,
+            refo_lp: false,
+            vlo_auto_off: true,
+            old_mclk_freq: mclk_freq,
+        }
+    }
+}
+
+impl<XT1: Xt1State> ClockConfig<NoClockDefined, NoClockDefined, XT1> {
+    /// Configure MCLK, SMCLK, and ACLK with the settings of a `ClockTree`, which can be checked at
+    /// compile time. XT1 has to be enabled first if the tree sources a clock from it. Panics if
+    /// XT1 is off or runs at a different frequency than the one the tree was computed with.
+    #[inline]
+    pub fn clock_tree<SMCLK>(
+        mut self,
+        tree: ClockTree<SMCLK>,
+    ) -> ClockConfig<MclkDefined, SMCLK, XT1> {
+        if let MclkSel::Xt1(freq) = tree.mclk_sel {
+            assert!(self.xt1.freq() == Some(freq), "XT1 doesn't match ClockTree");
+        }
+        if let AclkSel::Xt1(freq) = tree.aclk_sel {
+            let xt1_freq = self.xt1.freq().expect("XT1 doesn't match ClockTree");
+            assert!(
+                AclkSel::xt1_divided(xt1_freq, tree.aclk_div).freq() == freq,
+                "XT1 doesn't match ClockTree"
+            );
+            self.xt1.set_aclk_div(tree.aclk_div);
+        }
+        ClockConfig {
+            mclk_div: tree.mclk_div,
+            aclk_sel: tree.aclk_sel,
+            ..make_clkconf!(self, MclkDefined(tree.mclk_sel), tree.smclk)
+        }
+    }
+}
+
+/// Status of the XT1 oscillator
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Xt1Status {
+    /// XT1 is running normally
+    Running,
+    /// XT1 has faulted, so every clock sourced from XT1 has automatically fallen back to REFOCLK
+    FallbackToRefo,
+}
+
+/// Oscillator fault monitor, used to detect XT1 faults after the clocks have been frozen
+///
+/// When XT1 faults, the hardware sets the oscillator fault flag, which can trigger a non-maskable
+/// interrupt, and switches ACLK, MCLK, and the FLL reference over to REFOCLK if they were sourced
+/// from XT1. The clocks switch back to XT1 only once the fault has been cleared.
+pub struct OscFault<XT1>(XT1);
+
+impl<XT1: Xt1Enabled> OscFault<XT1> {
+    /// Read the XT1 status without clearing any fault flags
+    #[inline]
+    pub fn xt1_status(&self) -> Xt1Status {
+        let cs = unsafe { &*pac::CS::ptr() };
+        if cs.csctl7.read().xt1offg().bit() {
+            Xt1Status::FallbackToRefo
+        } else {
+            Xt1Status::Running
+        }
+    }
+
+    /// Attempt to clear the XT1 fault flag and the oscillator fault interrupt flag, returning the
+    /// XT1 status afterwards. If the fault persists, the hardware sets the flags again and the
+    /// clocks stay on REFOCLK. Should be called from the NMI handler if fault interrupts are
+    /// enabled.
+    #[inline]
+    pub fn clear_fault(&mut self) -> Xt1Status {
+        let cs = unsafe { &*pac::CS::ptr() };
+        let sfr = unsafe { &*pac::SFR::ptr() };
+        unsafe { cs.csctl7.clear_bits(|w| w.xt1offg().clear_bit()) };
+        unsafe { sfr.sfrifg1.clear_bits(|w| w.ofifg().clear_bit()) };
+        self.xt1_status()
+    }
+
+    /// Enable the oscillator fault non-maskable interrupt
+    #[inline]
+    pub fn enable_interrupts(&mut self) {
+        let sfr = unsafe { &*pac::SFR::ptr() };
+        unsafe { sfr.sfrie1.set_bits(|w| w.ofie().set_bit()) };
+    }
+
+    /// Disable the oscillator fault non-maskable interrupt
+    #[inline]
+    pub fn disable_interrupts(&mut self) {
+        let sfr = unsafe { &*pac::SFR::ptr() };
+        unsafe { sfr.sfrie1.clear_bits(|w| w.ofie().clear_bit()) };
+    }
+}
+
+/// Highest supported MCLK frequency
+const MCLK_MAX_FREQ: u32 = 24_000_000;
+// MCLK frequency after a reset, which is DCOCLKDIV with the default FLL multiplier of 32
+const RESET_MCLK_FREQ: u32 = 32 * REFOCLK as u32;
+/// Highest supported ACLK frequency
+const ACLK_MAX_FREQ: u32 = 40_000;
+
+/// Clock tree settings evaluated at compile time
+///
+/// Holds the frequency settings of `ClockConfig` in a form that `const fn`s can work with, so the
+/// resulting MCLK, SMCLK, and ACLK frequencies and FRAM wait states can be computed in a `const`
+/// item. Settings the hardware can't achieve cause a panic, which fails the build when evaluated in
+/// a const context. Passing the tree to `ClockConfig::clock_tree` configures exactly those
+/// settings, so the clock objects returned by `freeze` run at the frequencies computed here.
+#[derive(Clone, Copy)]
+pub struct ClockTree<SMCLK = SmclkDisabled> {
+    mclk_sel: MclkSel,
+    mclk_div: MclkDiv,
+    smclk: SMCLK,
+    aclk_sel: AclkSel,
+    // Only used when ACLK is sourced from XT1
+    aclk_div: AclkDiv,
+}
+
+impl ClockTree {
+    #[inline]
+    const fn with_mclk(mclk_sel: MclkSel, mclk_div: MclkDiv) -> Self {
+        ClockTree {
+            mclk_sel,
+            mclk_div,
+            smclk: SmclkDisabled,
+            aclk_sel: AclkSel::Refoclk,
+            aclk_div: AclkDiv::_1,
+        }
+    }
+
+    /// MCLK sourced from REFOCLK, with SMCLK disabled and ACLK sourced from REFOCLK
+    #[inline]
+    pub const fn mclk_refoclk(mclk_div: MclkDiv) -> Self {
+        Self::with_mclk(MclkSel::Refoclk, mclk_div)
+    }
+
+    /// MCLK sourced from VLOCLK, with SMCLK disabled and ACLK sourced from REFOCLK
+    #[inline]
+    pub const fn mclk_vcoclk(mclk_div: MclkDiv) -> Self {
+        Self::with_mclk(MclkSel::Vloclk, mclk_div)
+    }
+
+    /// MCLK sourced from DCOCLK using factory trim, with SMCLK disabled and ACLK sourced from
+    /// REFOCLK
+    #[inline]
+    pub const fn mclk_dcoclk(target_freq: DcoclkFreqSel, mclk_div: MclkDiv) -> Self {
+        Self::with_mclk(MclkSel::Dcoclk(target_freq), mclk_div)
+    }
+
+    /// MCLK sourced from DCOCLK using software trim, with SMCLK disabled and ACLK sourced from
+    /// REFOCLK. Panics if the target is outside of the 1 MHz to 24 MHz DCO range, as with
+    /// `ClockConfig::mclk_dco_hz`.
+    #[inline]
+    pub const fn mclk_dco_hz(target: Hertz, mclk_div: MclkDiv) -> Self {
+        Self::with_mclk(MclkSel::DcoclkTrimmed(DcoTrim::new(target.raw())), mclk_div)
+    }
+
+    /// MCLK sourced from an XT1 crystal or external clock running at `xt1_freq`, with SMCLK
+    /// disabled and ACLK sourced from REFOCLK. Panics if XT1 runs above 24 MHz.
+    #[inline]
+    pub const fn mclk_xt1(xt1_freq: Hertz, mclk_div: MclkDiv) -> Self {
+        assert!(xt1_freq.raw() <= MCLK_MAX_FREQ, "MCLK above 24 MHz");
+        Self::with_mclk(MclkSel::Xt1(xt1_freq.raw()), mclk_div)
+    }
+
+    /// Enable SMCLK and set the SMCLK divider, which divides the MCLK frequency
+    #[inline]
+    pub const fn smclk_on(self, div: SmclkDiv) -> ClockTree<SmclkDefined> {
+        ClockTree {
+            mclk_sel: self.mclk_sel,
+            mclk_div: self.mclk_div,
+            smclk: SmclkDefined(div),
+            aclk_sel: self.aclk_sel,
+            aclk_div: self.aclk_div,
+        }
+    }
+}
+
+impl ClockTree<SmclkDefined> {
+    /// Disable SMCLK
+    #[inline]
+    pub const fn smclk_off(self) -> ClockTree {
+        ClockTree {
+            mclk_sel: self.mclk_sel,
+            mclk_div: self.mclk_div,
+            smclk: SmclkDisabled,
+            aclk_sel: self.aclk_sel,
+            aclk_div: self.aclk_div,
+        }
+    }
+
+    /// SMCLK frequency
+    #[inline]
+    pub const fn smclk(&self) -> Hertz {
+        Hertz::from_raw(smclk_freq(self.mclk().raw(), self.smclk.0))
+    }
+}
+
+impl<SMCLK> ClockTree<SMCLK> {
+    /// Select REFOCLK for ACLK
+    #[inline]
+    pub const fn aclk_refoclk(mut self) -> Self {
+        self.aclk_sel = AclkSel::Refoclk;
+        self
+    }
+
+    /// Select VLOCLK for ACLK
+    #[inline]
+    pub const fn aclk_vloclk(mut self) -> Self {
+        self.aclk_sel = AclkSel::Vloclk;
+        self
+    }
+
+    /// Select XT1CLK running at `xt1_freq` for ACLK, divided by `aclk_div`. Panics if the
+    /// resulting ACLK frequency exceeds 40 kHz.
+    #[inline]
+    pub const fn aclk_xt1(mut self, xt1_freq: Hertz, aclk_div: AclkDiv) -> Self {
+        self.aclk_sel = AclkSel::xt1_divided(xt1_freq.raw(), aclk_div);
+        self.aclk_div = aclk_div;
+        self
+    }
+
+    /// MCLK frequency
+    #[inline]
+    pub const fn mclk(&self) -> Hertz {
+        Hertz::from_raw(self.mclk_sel.divided_freq(self.mclk_div))
+    }
+
+    /// ACLK frequency
+    #[inline]
+    pub const fn aclk(&self) -> Hertz {
+        Hertz::from_raw(self.aclk_sel.freq() as u32)
+    }
+
+    /// FRAM wait states that `freeze` will configure for this MCLK frequency
+    #[inline]
+    pub const fn wait_states(&self) -> WaitStates {
+        wait_states(self.mclk().raw())
+    }
+}
+
+/// Typestate for clock objects whose conditional requests are enabled, which lets peripherals
+/// keep the clock running in low power modes where it would otherwise be off
+pub struct ReqEnabled;
+/// Typestate for clock objects whose conditional requests are disabled, so the clock stops in any
+/// low power mode that turns it off, even if a peripheral is still using it
+pub struct ReqDisabled;
+
+/// MCLK clock object
+///
+/// Holds on to the CS peripheral while the clocks are frozen, which `ClockConfig::reconfigure`
+/// takes back. The `SMCLK` typestate records whether SMCLK was enabled, so an
+/// `Mclk<SmclkDisabled>` proves that no peripheral can be clocked from SMCLK.
+pub struct Mclk<SMCLK = SmclkDefined>(Hertz, pac::CS, PhantomData<SMCLK>);
+
+impl<SMCLK> Mclk<SMCLK> {
+    #[inline(always)]
+    fn new(freq: u32, cs: pac::CS) -> Self {
+        Mclk(Hertz::from_raw(freq), cs, PhantomData)
     }
 }
 
 /// SMCLK clock object
-pub struct Smclk(u32);
+///
+/// Peripherals can only be clocked from SMCLK while its conditional requests are enabled, unless
+/// the request-disabled clock is explicitly borrowed with `assume_active`.
+#[repr(transparent)]
+pub struct Smclk<REQ = ReqEnabled>(Hertz, PhantomData<REQ>);
 /// ACLK clock object
-pub struct Aclk(u16);
+///
+/// Peripherals can only be clocked from ACLK while its conditional requests are enabled, unless
+/// the request-disabled clock is explicitly borrowed with `assume_active`.
+#[repr(transparent)]
+pub struct Aclk<REQ = ReqEnabled>(Hertz, PhantomData<REQ>);
+
+impl Smclk {
+    #[inline(always)]
+    fn new(freq: u32) -> Self {
+        Smclk(Hertz::from_raw(freq), PhantomData)
+    }
+
+    /// Disable conditional requests for SMCLK, so SMCLK stops in LPM3 and LPM4 even if a
+    /// peripheral requests it
+    #[inline]
+    pub fn disable_requests(self) -> Smclk<ReqDisabled> {
+        let cs = unsafe { &*pac::CS::ptr() };
+        unsafe { cs.csctl8.clear_bits(|w| w.smclkreqen().clear_bit()) };
+        Smclk(self.0, PhantomData)
+    }
+}
+
+impl Smclk<ReqDisabled> {
+    /// Re-enable conditional requests for SMCLK
+    #[inline]
+    pub fn enable_requests(self) -> Smclk {
+        let cs = unsafe { &*pac::CS::ptr() };
+        unsafe { cs.csctl8.set_bits(|w| w.smclkreqen().set_bit()) };
+        Smclk(self.0, PhantomData)
+    }
+
+    /// Borrow SMCLK for configuring a peripheral even though its requests are disabled. The
+    /// peripheral will only be clocked while the CPU is in active mode or LPM0.
+    #[inline]
+    pub fn assume_active(&self) -> &Smclk {
+        // Smclk is transparent over its frequency, so the typestate doesn't affect the layout
+        unsafe { &*(self as *const Self as *const Smclk) }
+    }
+}
+
+impl Aclk {
+    #[inline(always)]
+    fn new(freq: u32) -> Self {
+        Aclk(Hertz::from_raw(freq), PhantomData)
+    }
+
+    /// Disable conditional requests for ACLK, so ACLK stops in LPM4 even if a peripheral requests
+    /// it
+    #[inline]
+    pub fn disable_requests(self) -> Aclk<ReqDisabled> {
+        let cs = unsafe { &*pac::CS::ptr() };
+        unsafe { cs.csctl8.clear_bits(|w| w.aclkreqen().clear_bit()) };
+        Aclk(self.0, PhantomData)
+    }
+}
+
+impl Aclk<ReqDisabled> {
+    /// Re-enable conditional requests for ACLK
+    #[inline]
+    pub fn enable_requests(self) -> Aclk {
+        let cs = unsafe { &*pac::CS::ptr() };
+        unsafe { cs.csctl8.set_bits(|w| w.aclkreqen().set_bit()) };
+        Aclk(self.0, PhantomData)
+    }
+
+    /// Borrow ACLK for configuring a peripheral even though its requests are disabled. The
+    /// peripheral will only be clocked while the CPU is in active mode or LPM0 to LPM3.
+    #[inline]
+    pub fn assume_active(&self) -> &Aclk {
+        // Aclk is transparent over its frequency, so the typestate doesn't affect the layout
+        unsafe { &*(self as *const Self as *const Aclk) }
+    }
+}
 
 /// Trait for configured clock objects
 pub trait Clock {
@@ -382,24 +1497,135 @@ pub trait Clock {
     fn freq(&self) -> Self::Freq;
 }
 
-impl Clock for Smclk {
-    type Freq = u32;
+impl<SMCLK> Clock for Mclk<SMCLK> {
+    type Freq = Hertz;
+
+    #[inline]
+    fn freq(&self) -> Hertz {
+        self.0
+    }
+}
+
+impl<REQ> Clock for Smclk<REQ> {
+    type Freq = Hertz;
 
-    /// Returning a 32-bit frequency may seem suspect, since we're on a 16-bit system, but it is
-    /// required as SMCLK can go up to 24 MHz. Clock frequencies are usually for initialization
-    /// tasks such as computing baud rates, which should be optimized away, avoiding the extra cost
-    /// of 32-bit computations.
     #[inline]
-    fn freq(&self) -> u32 {
+    fn freq(&self) -> Hertz {
         self.0
     }
 }
 
-impl Clock for Aclk {
-    type Freq = u16;
+impl<REQ> Clock for Aclk<REQ> {
+    type Freq = Hertz;
 
     #[inline]
-    fn freq(&self) -> u16 {
+    fn freq(&self) -> Hertz {
         self.0
     }
 }
+
+mod sealed {
+    use super::*;
+
+    pub trait SealedMclkOutPin {}
+    pub trait SealedSmclkOutPin {}
+    pub trait SealedAclkOutPin {}
+
+    impl SealedMclkOutPin for Pin<P2, Pin6, Alternate1<Output>> {}
+    impl SealedMclkOutPin for Pin<P3, Pin0, Alternate1<Output>> {}
+    impl SealedSmclkOutPin for Pin<P1, Pin0, Alternate2<Output>> {}
+    impl SealedSmclkOutPin for Pin<P3, Pin4, Alternate1<Output>> {}
+    impl SealedAclkOutPin for Pin<P1, Pin1, Alternate2<Output>> {}
+}
+
+/// Marker trait for GPIO pins that can output MCLK
+pub trait MclkOutPin: sealed::SealedMclkOutPin {}
+impl MclkOutPin for Pin<P2, Pin6, Alternate1<Output>> {}
+impl MclkOutPin for Pin<P3, Pin0, Alternate1<Output>> {}
+
+/// Marker trait for GPIO pins that can output SMCLK
+pub trait SmclkOutPin: sealed::SealedSmclkOutPin {}
+impl SmclkOutPin for Pin<P1, Pin0, Alternate2<Output>> {}
+impl SmclkOutPin for Pin<P3, Pin4, Alternate1<Output>> {}
+
+/// Marker trait for GPIO pins that can output ACLK
+pub trait AclkOutPin: sealed::SealedAclkOutPin {}
+impl AclkOutPin for Pin<P1, Pin1, Alternate2<Output>> {}
+
+/// MCLK output on a GPIO pin
+///
+/// Borrows the MCLK clock object, so the clocks can't be reconfigured while the output exists.
+pub struct MclkOut<'a, PIN: MclkOutPin> {
+    pin: PIN,
+    _mclk: PhantomData<&'a ()>,
+}
+
+impl<'a, PIN: MclkOutPin> MclkOut<'a, PIN> {
+    /// Output MCLK on the pin
+    #[inline]
+    pub fn new<SMCLK>(pin: PIN, _mclk: &'a Mclk<SMCLK>) -> Self {
+        MclkOut {
+            pin,
+            _mclk: PhantomData,
+        }
+    }
+
+    /// Stop using the pin as a clock output and return it. The clock continues to be driven onto
+    /// the pin until it's converted back to GPIO.
+    #[inline]
+    pub fn free(self) -> PIN {
+        self.pin
+    }
+}
+
+/// SMCLK output on a GPIO pin
+///
+/// Borrows the SMCLK clock object, so the clocks can't be reconfigured while the output exists.
+pub struct SmclkOut<'a, PIN: SmclkOutPin> {
+    pin: PIN,
+    _smclk: PhantomData<&'a Smclk>,
+}
+
+impl<'a, PIN: SmclkOutPin> SmclkOut<'a, PIN> {
+    /// Output SMCLK on the pin
+    #[inline]
+    pub fn new<REQ>(pin: PIN, _smclk: &'a Smclk<REQ>) -> Self {
+        SmclkOut {
+            pin,
+            _smclk: PhantomData,
+        }
+    }
+
+    /// Stop using the pin as a clock output and return it. The clock continues to be driven onto
+    /// the pin until it's converted back to GPIO.
+    #[inline]
+    pub fn free(self) -> PIN {
+        self.pin
+    }
+}
+
+/// ACLK output on a GPIO pin
+///
+/// Borrows the ACLK clock object, so the clocks can't be reconfigured while the output exists.
+pub struct AclkOut<'a, PIN: AclkOutPin> {
+    pin: PIN,
+    _aclk: PhantomData<&'a Aclk>,
+}
+
+impl<'a, PIN: AclkOutPin> AclkOut<'a, PIN> {
+    /// Output ACLK on the pin
+    #[inline]
+    pub fn new<REQ>(pin: PIN, _aclk: &'a Aclk<REQ>) -> Self {
+        AclkOut {
+            pin,
+            _aclk: PhantomData,
+        }
+    }
+
+    /// Stop using the pin as a clock output and return it. The clock continues to be driven onto
+    /// the pin until it's converted back to GPIO.
+    #[inline]
+    pub fn free(self) -> PIN {
+        self.pin
+    }
+}
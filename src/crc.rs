@@ -0,0 +1,184 @@
+//! CRC16 module
+//!
+//! The CRC16 module computes CRC-CCITT (polynomial 0x1021) checksums in hardware, one byte per
+//! write. Data can be fed in two bit orders:
+//!
+//! * `InputOrder::Normal` writes to CRCDI, which shifts each byte in LSB first. This is the order
+//!   the device's own TLV and bootloader checksums use.
+//! * `InputOrder::Reversed` writes to CRCDIRB, which shifts each byte in MSB first, matching the
+//!   usual software CRC-CCITT implementations, such as CRC-16/CCITT-FALSE with a 0xFFFF seed.
+//!
+//! The result can also be read in both bit orders. `Crc::digest` wraps a seed, a series of
+//! updates, and the final read, for checksumming packets or FRAM blocks in pieces:
+//!
+//! ```ignore
+//! let mut crc = Crc::new(periph.CRC);
+//! let mut digest = crc.digest(0xFFFF, InputOrder::Reversed);
+//! digest.update(header);
+//! digest.update(payload);
+//! let checksum = digest.finish();
+//! ```
+
+use core::ptr;
+use msp430fr2355 as pac;
+use pac::CRC;
+
+/// Bit order in which data is shifted into the CRC
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InputOrder {
+    /// Each byte LSB first, through CRCDI
+    Normal,
+    /// Each byte MSB first, through CRCDIRB
+    Reversed,
+}
+
+/// CRC16 module
+pub struct Crc {
+    periph: CRC,
+}
+
+impl Crc {
+    /// Take the CRC peripheral, seeded with 0xFFFF
+    #[inline]
+    pub fn new(periph: CRC) -> Self {
+        let mut crc = Crc { periph };
+        crc.seed(0xFFFF);
+        crc
+    }
+
+    /// Release the CRC peripheral
+    #[inline]
+    pub fn free(self) -> CRC {
+        self.periph
+    }
+
+    /// Start a new computation from `seed`
+    #[inline]
+    pub fn seed(&mut self, seed: u16) {
+        self.periph.crcinires.write(|w| unsafe { w.bits(seed) });
+    }
+
+    /// Shift a byte into the CRC
+    #[inline]
+    pub fn feed_byte(&mut self, byte: u8, order: InputOrder) {
+        let reg = match order {
+            InputOrder::Normal => self.periph.crcdi.as_ptr(),
+            InputOrder::Reversed => self.periph.crcdirb.as_ptr(),
+        };
+        // A byte write to the low byte of the data register shifts in just that byte
+        unsafe { ptr::write_volatile(reg as *mut u8, byte) };
+    }
+
+    /// Shift a word into the CRC. With `InputOrder::Normal` the low byte goes first, while with
+    /// `InputOrder::Reversed` the whole word is shifted in MSB first, so the high byte goes first.
+    #[inline]
+    pub fn feed_word(&mut self, word: u16, order: InputOrder) {
+        match order {
+            InputOrder::Normal => self.periph.crcdi.write(|w| unsafe { w.bits(word) }),
+            InputOrder::Reversed => self.periph.crcdirb.write(|w| unsafe { w.bits(word) }),
+        }
+    }
+
+    /// Shift a slice of bytes into the CRC
+    #[inline]
+    pub fn feed(&mut self, bytes: &[u8], order: InputOrder) {
+        for &byte in bytes {
+            self.feed_byte(byte, order);
+        }
+    }
+
+    /// Shift a slice of words into the CRC, each as with `feed_word`
+    #[inline]
+    pub fn feed_words(&mut self, words: &[u16], order: InputOrder) {
+        for &word in words {
+            self.feed_word(word, order);
+        }
+    }
+
+    /// Current CRC result
+    #[inline]
+    pub fn result(&self) -> u16 {
+        self.periph.crcinires.read().bits()
+    }
+
+    /// Current CRC result with its bits reversed
+    #[inline]
+    pub fn result_reversed(&self) -> u16 {
+        self.periph.crcresr.read().bits()
+    }
+
+    /// Start a streaming computation from `seed`, feeding data in `order`
+    #[inline]
+    pub fn digest(&mut self, seed: u16, order: InputOrder) -> Digest<'_> {
+        self.seed(seed);
+        Digest { crc: self, order }
+    }
+
+    /// Compute the CRC of `bytes` in one go, starting from `seed`
+    #[inline]
+    pub fn checksum(&mut self, seed: u16, order: InputOrder, bytes: &[u8]) -> u16 {
+        let mut digest = self.digest(seed, order);
+        digest.update(bytes);
+        digest.finish()
+    }
+}
+
+/// Streaming CRC computation, which holds the CRC module until it's finished
+pub struct Digest<'a> {
+    crc: &'a mut Crc,
+    order: InputOrder,
+}
+
+impl Digest<'_> {
+    /// Shift more bytes into the CRC
+    #[inline]
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.crc.feed(bytes, self.order);
+    }
+
+    /// Shift more words into the CRC, each as with `Crc::feed_word`
+    #[inline]
+    pub fn update_words(&mut self, words: &[u16]) {
+        self.crc.feed_words(words, self.order);
+    }
+
+    /// CRC of the data so far, without ending the computation
+    #[inline]
+    pub fn current(&self) -> u16 {
+        self.crc.result()
+    }
+
+    /// End the computation, returning the CRC
+    #[inline]
+    pub fn finish(self) -> u16 {
+        self.crc.result()
+    }
+
+    /// End the computation, returning the CRC with its bits reversed
+    #[inline]
+    pub fn finish_reversed(self) -> u16 {
+        self.crc.result_reversed()
+    }
+}
+
+// Software CRC-16-CCITT with the bit order of `InputOrder::Normal`, for checksums that are
+// computed without taking the CRC16 module, like those of the TLV area and FRAM stores
+#[inline]
+pub(crate) fn crc16(bytes: &[u8]) -> u16 {
+    crc16_update(0xFFFF, bytes)
+}
+
+// Continue a CRC-16 computation over more bytes
+pub(crate) fn crc16_update(mut crc: u16, bytes: &[u8]) -> u16 {
+    for &byte in bytes {
+        crc ^= (byte.reverse_bits() as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
@@ -1,36 +1,193 @@
 //! Embedded hal delay implementation
-use crate::hal::blocking::delay::DelayMs;
-use msp430::asm;
+//!
+//! `Delay` busy-waits on the CPU with a calibrated cycle loop, and is returned by the clock
+//! configuration. `TimerDelay` waits on a TimerB main timer instead, so its accuracy depends only
+//! on the timer clock.
+use crate::hal::blocking::delay::{DelayMs, DelayUs};
+use crate::time::Hertz;
+use crate::timer::{CapCmp, Timer, TimerPeriph, CCR0};
+use core::arch::asm;
+
+// Longest duration waited on in one go, chosen so that the cycle count can't overflow a u32 at
+// the maximum MCLK frequency of 24 MHz
+const MAX_CHUNK: u32 = 100_000;
 
 /// Delay provider struct
 pub struct Delay {
-    nops_per_ms: u16,
+    cycles_per_ms: u32,
 }
 
 impl Delay {
     /// Creates a new Delay provider for a given clock frequency
-    pub fn new(freq: u32) -> Self {
-        // ~21 nops needed per 2^20 MHz to delay 1 ms
-        let nops: u32 = 210 * (freq >> 20);
+    pub fn new(freq: Hertz) -> Self {
         Delay {
-            nops_per_ms: (nops as u16),
+            // Round up so that delays are never shorter than requested
+            cycles_per_ms: freq.raw().div_ceil(1000),
         }
     }
 }
 
+// Busy-wait for at least `cycles` MCLK cycles. Each loop iteration takes exactly 4 cycles: 1 each
+// for `sub` and `subc`, whose operands come from the constant generator, and 2 for `jc`.
+#[inline(always)]
+pub(crate) fn delay_cycles(cycles: u32) {
+    let iters = cycles.div_ceil(4);
+    if iters == 0 {
+        return;
+    }
+    // The loop body runs once more than the initial count
+    let count = iters - 1;
+    unsafe {
+        asm!(
+            "1:",
+            "sub #1, {lo}",
+            "subc #0, {hi}",
+            "jc 1b",
+            lo = inout(reg) count as u16 => _,
+            hi = inout(reg) (count >> 16) as u16 => _,
+            options(nomem, nostack)
+        )
+    };
+}
+
+impl DelayMs<u32> for Delay {
+    fn delay_ms(&mut self, mut ms: u32) {
+        while ms > 0 {
+            let chunk = ms.min(MAX_CHUNK);
+            delay_cycles(chunk * self.cycles_per_ms);
+            ms -= chunk;
+        }
+    }
+}
+
+impl DelayMs<u16> for Delay {
+    #[inline]
+    fn delay_ms(&mut self, ms: u16) {
+        self.delay_ms(ms as u32);
+    }
+}
+
 impl DelayMs<u8> for Delay {
     #[inline]
     fn delay_ms(&mut self, ms: u8) {
-        self.delay_ms(ms as u16);
+        self.delay_ms(ms as u32);
     }
 }
 
-impl DelayMs<u16> for Delay {
-    fn delay_ms(&mut self, ms: u16) {
-        for _ in 0..ms {
-            for _ in 0..self.nops_per_ms {
-                asm::nop();
+impl DelayUs<u32> for Delay {
+    fn delay_us(&mut self, mut us: u32) {
+        while us > 0 {
+            let chunk = us.min(MAX_CHUNK);
+            delay_cycles((chunk * self.cycles_per_ms).div_ceil(1000));
+            us -= chunk;
+        }
+    }
+}
+
+impl DelayUs<u16> for Delay {
+    #[inline]
+    fn delay_us(&mut self, us: u16) {
+        self.delay_us(us as u32);
+    }
+}
+
+impl DelayUs<u8> for Delay {
+    #[inline]
+    fn delay_us(&mut self, us: u8) {
+        self.delay_us(us as u32);
+    }
+}
+
+/// Delay provider backed by a TimerB main timer
+///
+/// The timer is reprogrammed for every delay, so it can't be used for anything else until it is
+/// released with `free`. Timer interrupts should be disabled while delaying. Every delay lasts at
+/// least 2 timer ticks, since the timer can't count a period of 1 tick.
+pub struct TimerDelay<'a, T: TimerPeriph> {
+    timer: Timer<'a, T>,
+}
+
+impl<'a, T: TimerPeriph + CapCmp<CCR0>> TimerDelay<'a, T> {
+    /// Create a delay provider out of a main timer
+    #[inline]
+    pub fn new(timer: Timer<'a, T>) -> Self {
+        TimerDelay { timer }
+    }
+
+    /// Release the main timer
+    #[inline]
+    pub fn free(self) -> Timer<'a, T> {
+        self.timer
+    }
+
+    // Wait for `ticks` periods of the timer clock, in chunks of 2 to 2^16 ticks. A chunk of 1
+    // tick would need a CCR0 of 0, which halts the timer in up mode, so delays shorter than 2 ticks
+    // are rounded up to 2, and a remainder of 1 tick is folded into the previous chunk.
+    fn delay_ticks(&mut self, ticks: u64) {
+        let timer = unsafe { T::steal() };
+        let mut ticks = ticks.max(2);
+        while ticks > 0 {
+            let mut chunk = ticks.min(0x1_0000);
+            if ticks - chunk == 1 {
+                chunk -= 1;
             }
+            timer.stop();
+            timer.set_ccrn((chunk - 1) as u16);
+            timer.upmode();
+            while !timer.tbifg_rd() {}
+            ticks -= chunk;
         }
+        timer.stop();
+        timer.tbifg_clr();
+    }
+
+    // Number of timer ticks in `amount` units of `1 / per_sec` seconds, rounded up
+    #[inline]
+    fn ticks(&self, amount: u32, per_sec: u64) -> u64 {
+        (self.timer.freq().raw() as u64 * amount as u64).div_ceil(per_sec)
+    }
+}
+
+impl<T: TimerPeriph + CapCmp<CCR0>> DelayMs<u32> for TimerDelay<'_, T> {
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        let ticks = self.ticks(ms, 1_000);
+        self.delay_ticks(ticks);
+    }
+}
+
+impl<T: TimerPeriph + CapCmp<CCR0>> DelayMs<u16> for TimerDelay<'_, T> {
+    #[inline]
+    fn delay_ms(&mut self, ms: u16) {
+        self.delay_ms(ms as u32);
+    }
+}
+
+impl<T: TimerPeriph + CapCmp<CCR0>> DelayMs<u8> for TimerDelay<'_, T> {
+    #[inline]
+    fn delay_ms(&mut self, ms: u8) {
+        self.delay_ms(ms as u32);
+    }
+}
+
+impl<T: TimerPeriph + CapCmp<CCR0>> DelayUs<u32> for TimerDelay<'_, T> {
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        let ticks = self.ticks(us, 1_000_000);
+        self.delay_ticks(ticks);
+    }
+}
+
+impl<T: TimerPeriph + CapCmp<CCR0>> DelayUs<u16> for TimerDelay<'_, T> {
+    #[inline]
+    fn delay_us(&mut self, us: u16) {
+        self.delay_us(us as u32);
+    }
+}
+
+impl<T: TimerPeriph + CapCmp<CCR0>> DelayUs<u8> for TimerDelay<'_, T> {
+    #[inline]
+    fn delay_us(&mut self, us: u8) {
+        self.delay_us(us as u32);
     }
 }
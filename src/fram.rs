@@ -1,7 +1,50 @@
 //! FRAM controller
+//!
+//! Program and information FRAM are write-protected by the PFWP and DFWP bits in SYSCFG0. Writes
+//! to non-volatile data go through a `WriteGuard` from `Fram::unlock_program` or
+//! `Fram::unlock_info`, which lifts the protection until it is dropped. The guard's type names the
+//! region it unlocks, so a guard for information FRAM can't be used to write program FRAM.
+//!
+//! `Persistent<T>` wraps a variable that lives in program FRAM and keeps its value across resets
+//! and power cycles. Place it in the `.persistent` section, which `memory.x` must put in ROM:
+//!
+//! ```text
+//! SECTIONS
+//! {
+//!   .persistent : ALIGN(2)
+//!   {
+//!     *(.persistent .persistent.*);
+//!     . = ALIGN(2);
+//!   } > ROM
+//! } INSERT AFTER .rodata;
+//! ```
+//!
+//! ```ignore
+//! #[link_section = ".persistent"]
+//! static BOOT_COUNT: Persistent<u16> = Persistent::new(0);
+//!
+//! let mut guard = fram.unlock_program();
+//! BOOT_COUNT.set(&mut guard, BOOT_COUNT.get() + 1);
+//! ```
+//!
+//! The FRAM controller detects correctable and uncorrectable bit errors. `Fram::errors` reports
+//! the errors seen since they were last cleared, so long-lived devices can log degrading memory,
+//! and the controller can also raise an NMI or a reset when it detects them.
+//!
+//! `FramStorage` exposes a `Persistent` byte array, or the whole of information FRAM, through the
+//! `embedded_storage::nor_flash` traits, so that storage crates can run on top of FRAM. FRAM is
+//! byte-writable and needs no erasing, so any byte can be rewritten at will. Erasing fills the range
+//! with 0xFF, the erased value that storage crates expect to read back.
 
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ptr;
+use embedded_storage::nor_flash::{
+    check_erase, check_read, check_write, ErrorType, MultiwriteNorFlash, NorFlash,
+    NorFlashErrorKind, ReadNorFlash,
+};
 use msp430fr2355 as pac;
-use pac::FRCTL;
+use pac::{FRCTL, SYS};
 
 /// FRAM controller
 pub struct Fram {
@@ -18,6 +61,7 @@ impl Fram {
 const PASSWORD: u8 = 0xA5;
 
 /// FRAM wait states
+#[derive(Clone, Copy)]
 pub enum WaitStates {
     /// No wait
     Wait0,
@@ -46,4 +90,295 @@ impl Fram {
             .frctl0
             .write(|w| w.frctlpw().bits(PASSWORD).nwaits().bits(wait as u8));
     }
+
+    #[inline]
+    pub(crate) fn wait_states(&self) -> u8 {
+        self.periph.frctl0.read().nwaits().bits()
+    }
+
+    /// Raise an NMI when a correctable bit error is detected
+    #[inline]
+    pub fn correctable_error_nmi(&mut self, enable: bool) {
+        self.with_unlocked(|f| f.gcctl0.modify(|_, w| w.cbdie().bit(enable)));
+    }
+
+    /// Select how the controller responds to uncorrectable bit errors
+    #[inline]
+    pub fn uncorrectable_error_response(&mut self, response: ErrorResponse) {
+        let (nmi, reset) = match response {
+            ErrorResponse::FlagOnly => (false, false),
+            ErrorResponse::Nmi => (true, false),
+            ErrorResponse::Reset => (false, true),
+        };
+        self.with_unlocked(|f| {
+            f.gcctl0
+                .modify(|_, w| w.ubdie().bit(nmi).ubdrsten().bit(reset))
+        });
+    }
+
+    /// Bit and access time errors detected since the flags were last cleared
+    #[inline]
+    pub fn errors(&self) -> FramErrors {
+        FramErrors(self.periph.gcctl1.read().bits())
+    }
+
+    /// Clear the error flags
+    #[inline]
+    pub fn clear_errors(&mut self) {
+        self.with_unlocked(|f| f.gcctl1.write(|w| unsafe { w.bits(0) }));
+    }
+
+    // Run `f` with the FRAM controller registers unlocked. The password byte is written on its own
+    // so that the rest of FRCTL0 is left alone.
+    #[inline(always)]
+    fn with_unlocked<R>(&mut self, f: impl FnOnce(&FRCTL) -> R) -> R {
+        let pw = unsafe { (self.periph.frctl0.as_ptr() as *mut u8).add(1) };
+        unsafe { ptr::write_volatile(pw, PASSWORD) };
+        let res = f(&self.periph);
+        unsafe { ptr::write_volatile(pw, 0) };
+        res
+    }
+
+    /// Lifts the write protection on program FRAM, including `Persistent` variables, until the
+    /// returned guard is dropped
+    #[inline]
+    pub fn unlock_program(&mut self) -> WriteGuard<'_, Program> {
+        self.unlock()
+    }
+
+    /// Lifts the write protection on information FRAM until the returned guard is dropped
+    #[inline]
+    pub fn unlock_info(&mut self) -> WriteGuard<'_, Info> {
+        self.unlock()
+    }
+
+    #[inline]
+    fn unlock<R: FramRegion>(&mut self) -> WriteGuard<'_, R> {
+        R::set_protection(false);
+        WriteGuard {
+            _region: PhantomData,
+            _fram: PhantomData,
+        }
+    }
+}
+
+/// Response to an uncorrectable FRAM bit error
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ErrorResponse {
+    /// Only set the error flag
+    FlagOnly,
+    /// Set the error flag and raise an NMI
+    Nmi,
+    /// Reset the device, which reports `ResetCause::FramUncorrectable`
+    Reset,
+}
+
+/// FRAM error flags
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FramErrors(u16);
+
+impl FramErrors {
+    /// A correctable bit error was detected and corrected
+    #[inline]
+    pub fn correctable(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// An uncorrectable bit error was detected
+    #[inline]
+    pub fn uncorrectable(&self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+
+    /// FRAM was accessed with too few wait states for the MCLK frequency
+    #[inline]
+    pub fn access_time(&self) -> bool {
+        self.0 & (1 << 3) != 0
+    }
+}
+
+mod sealed {
+    use super::*;
+
+    pub trait SealedFramRegion {}
+
+    impl SealedFramRegion for Program {}
+    impl SealedFramRegion for Info {}
+}
+
+/// Typestate for program FRAM, which holds code, constants, and `Persistent` variables
+pub struct Program;
+/// Typestate for information FRAM
+pub struct Info;
+
+/// Marker trait for FRAM regions with their own write protection
+pub trait FramRegion: sealed::SealedFramRegion {
+    #[doc(hidden)]
+    fn set_protection(protect: bool);
+}
+
+impl FramRegion for Program {
+    #[inline(always)]
+    fn set_protection(protect: bool) {
+        let sys = unsafe { &*SYS::ptr() };
+        sys.syscfg0
+            .modify(|_, w| unsafe { w.frwppw().bits(PASSWORD) }.pfwp().bit(protect));
+    }
+}
+
+impl FramRegion for Info {
+    #[inline(always)]
+    fn set_protection(protect: bool) {
+        let sys = unsafe { &*SYS::ptr() };
+        sys.syscfg0
+            .modify(|_, w| unsafe { w.frwppw().bits(PASSWORD) }.dfwp().bit(protect));
+    }
 }
+
+/// Keeps an FRAM region writable while it exists, and restores its write protection on drop
+pub struct WriteGuard<'a, R: FramRegion> {
+    _region: PhantomData<R>,
+    _fram: PhantomData<&'a mut Fram>,
+}
+
+impl<R: FramRegion> Drop for WriteGuard<'_, R> {
+    #[inline]
+    fn drop(&mut self) {
+        R::set_protection(true);
+    }
+}
+
+/// Variable stored in program FRAM, which keeps its value across resets and power cycles
+///
+/// The initial value is only written when the device is programmed. Values are read and written
+/// with volatile accesses inside a critical section, so interrupts never see a partial update.
+#[repr(transparent)]
+pub struct Persistent<T>(UnsafeCell<T>);
+
+unsafe impl<T: Send> Sync for Persistent<T> {}
+
+impl<T> Persistent<T> {
+    /// Create a persistent variable with the value it holds after programming
+    #[inline]
+    pub const fn new(val: T) -> Self {
+        Persistent(UnsafeCell::new(val))
+    }
+
+    /// Write a new value, which needs program FRAM to be unlocked
+    #[inline]
+    pub fn set(&self, _guard: &mut WriteGuard<Program>, val: T) {
+        critical_section::with(|_| unsafe { ptr::write_volatile(self.0.get(), val) });
+    }
+
+    /// Modify the value in place, which needs program FRAM to be unlocked
+    #[inline]
+    pub fn update<R>(&self, _guard: &mut WriteGuard<Program>, f: impl FnOnce(&mut T) -> R) -> R {
+        critical_section::with(|_| f(unsafe { &mut *self.0.get() }))
+    }
+}
+
+impl<T> Persistent<T> {
+    // Raw pointer to the value, for byte-level access by the FRAM storage types
+    #[inline(always)]
+    pub(crate) fn as_ptr(&self) -> *mut T {
+        self.0.get()
+    }
+}
+
+impl<T: Copy> Persistent<T> {
+    /// Read the current value
+    #[inline]
+    pub fn get(&self) -> T {
+        critical_section::with(|_| unsafe { ptr::read_volatile(self.0.get()) })
+    }
+}
+
+// Information FRAM
+const INFO_START: usize = 0x1800;
+const INFO_LEN: usize = 0x200;
+
+/// FRAM region accessed through the `embedded_storage::nor_flash` traits
+///
+/// Reads, writes, and erases all have a granularity of one byte. Erasing sets every byte in the
+/// range to 0xFF.
+pub struct FramStorage<'a, R: FramRegion = Program> {
+    fram: &'a mut Fram,
+    base: *mut u8,
+    len: usize,
+    _region: PhantomData<R>,
+}
+
+impl<'a> FramStorage<'a, Program> {
+    /// Storage backed by a `Persistent` byte array in program FRAM, which must not be used for
+    /// anything else
+    #[inline]
+    pub fn new<const N: usize>(fram: &'a mut Fram, mem: &'static Persistent<[u8; N]>) -> Self {
+        FramStorage {
+            fram,
+            base: mem.as_ptr() as *mut u8,
+            len: N,
+            _region: PhantomData,
+        }
+    }
+}
+
+impl<'a> FramStorage<'a, Info> {
+    /// Storage backed by all 512 bytes of information FRAM
+    #[inline]
+    pub fn info(fram: &'a mut Fram) -> Self {
+        FramStorage {
+            fram,
+            base: INFO_START as *mut u8,
+            len: INFO_LEN,
+            _region: PhantomData,
+        }
+    }
+}
+
+impl<R: FramRegion> ErrorType for FramStorage<'_, R> {
+    type Error = NorFlashErrorKind;
+}
+
+impl<R: FramRegion> ReadNorFlash for FramStorage<'_, R> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        check_read(self, offset, bytes.len())?;
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = unsafe { ptr::read_volatile(self.base.add(offset as usize + i)) };
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.len
+    }
+}
+
+impl<R: FramRegion> NorFlash for FramStorage<'_, R> {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = 1;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        check_erase(self, from, to)?;
+        let base = self.base;
+        let _guard = self.fram.unlock::<R>();
+        for i in from as usize..to as usize {
+            unsafe { ptr::write_volatile(base.add(i), 0xFF) };
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        check_write(self, offset, bytes.len())?;
+        let base = self.base;
+        let _guard = self.fram.unlock::<R>();
+        for (i, &b) in bytes.iter().enumerate() {
+            unsafe { ptr::write_volatile(base.add(offset as usize + i), b) };
+        }
+        Ok(())
+    }
+}
+
+impl<R: FramRegion> MultiwriteNorFlash for FramStorage<'_, R> {}
@@ -9,6 +9,7 @@ pub enum Tbssel {
 }
 
 /// Timer clock divider
+#[derive(Clone, Copy)]
 pub enum TimerDiv {
     /// No division
     _1,
@@ -21,6 +22,7 @@ pub enum TimerDiv {
 }
 
 /// Timer expansion clock divider, applied on top of the normal clock divider
+#[derive(Clone, Copy)]
 pub enum TimerExDiv {
     /// No division
     _1,
@@ -9,7 +9,7 @@
 //! eUSCI_B1: {SCL:P4.7, SDA:P4.6}
 //!
 
-use crate::clock::{Aclk, Smclk};
+use crate::clock::{Aclk, Clock, Smclk};
 use crate::hw_traits::eusci::I2CUcbIfgOut;
 use crate::{
     gpio::{Alternate1, Pin, Pin2, Pin3, Pin6, Pin7, P1, P4},
@@ -22,6 +22,7 @@ use crate::{
         Ucssel,
     },
     pac,
+    time::{self, Hertz},
 };
 use core::marker::PhantomData;
 use msp430::asm;
@@ -82,9 +83,10 @@ impl From<GlitchFilter> for Ucglit {
 }
 
 ///Struct used to configure a I2C bus
-pub struct I2CBusConfig<USCI: EUsciI2CBus> {
+pub struct I2CBusConfig<'a, USCI: EUsciI2CBus> {
     usci: USCI,
     divisor: u16,
+    _clk: PhantomData<&'a ()>,
 
     // Register configs
     ctlw0: UcbCtlw0,
@@ -151,7 +153,7 @@ impl<DIR> From<Pin<P4, Pin6, Alternate1<DIR>>> for UsciB1SDAPin {
     }
 }
 
-impl<USCI: EUsciI2CBus> I2CBusConfig<USCI> {
+impl<'a, USCI: EUsciI2CBus> I2CBusConfig<'a, USCI> {
     /// Create a new configuration for setting up a EUSCI peripheral in I2C master mode
     pub fn new(usci: USCI) -> Self {
         let ctlw0 = UcbCtlw0 {
@@ -242,6 +244,7 @@ impl<USCI: EUsciI2CBus> I2CBusConfig<USCI> {
         I2CBusConfig {
             usci,
             divisor: 1,
+            _clk: PhantomData,
             ctlw0,
             ctlw1,
             i2coa0,
@@ -253,18 +256,20 @@ impl<USCI: EUsciI2CBus> I2CBusConfig<USCI> {
         }
     }
 
-    /// Configures this peripheral to use smclk
+    /// Configures this peripheral to use smclk, dividing it down so the SCL clock runs at no
+    /// more than `freq`
     #[inline]
-    pub fn use_smclk(&mut self, _smclk: &Smclk, clk_divisor: u16) {
+    pub fn use_smclk(&mut self, smclk: &'a Smclk, freq: Hertz) {
         self.ctlw0.ucssel = Ucssel::Smclk;
-        self.divisor = clk_divisor;
+        self.divisor = time::divisor(smclk.freq(), freq);
     }
 
-    /// Configures this peripheral to use aclk
+    /// Configures this peripheral to use aclk, dividing it down so the SCL clock runs at no
+    /// more than `freq`
     #[inline]
-    pub fn use_aclk(&mut self, _aclk: &Aclk, clk_divisor: u16) {
+    pub fn use_aclk(&mut self, aclk: &'a Aclk, freq: Hertz) {
         self.ctlw0.ucssel = Ucssel::Aclk;
-        self.divisor = clk_divisor;
+        self.divisor = time::divisor(aclk.freq(), freq);
     }
 
     /// Configures the glitch filter length for the SDA and SCL lines
@@ -278,9 +283,9 @@ impl<USCI: EUsciI2CBus> I2CBusConfig<USCI> {
         &self,
         _scl: C,
         _sdl: D,
-    ) -> SDL<USCI> {
+    ) -> SDL<'a, USCI> {
         self.configure();
-        SDL(PhantomData)
+        SDL(PhantomData, PhantomData)
     }
 
     /// Performs hardware configuration
@@ -305,7 +310,7 @@ impl<USCI: EUsciI2CBus> I2CBusConfig<USCI> {
 }
 
 /// I2C data pin
-pub struct SDL<USCI: EUsciI2CBus>(PhantomData<USCI>);
+pub struct SDL<'a, USCI: EUsciI2CBus>(PhantomData<USCI>, PhantomData<&'a ()>);
 
 /// I2C transmit/receive errors
 #[derive(Clone, Copy)]
@@ -318,7 +323,7 @@ pub enum I2CErr {
     ArbitrationLost,
 }
 
-impl<USCI: EUsciI2CBus> SDL<USCI> {
+impl<USCI: EUsciI2CBus> SDL<'_, USCI> {
     #[inline(always)]
     fn set_addressing_mode(&mut self, mode: AddressingMode) {
         let usci = unsafe { USCI::steal() };
@@ -446,7 +451,7 @@ impl<USCI: EUsciI2CBus> SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> Read<SevenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> Read<SevenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
         self.set_addressing_mode(AddressingMode::SevenBit);
@@ -455,7 +460,7 @@ impl<USCI: EUsciI2CBus> Read<SevenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> Read<TenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> Read<TenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn read(&mut self, address: u16, buffer: &mut [u8]) -> Result<(), Self::Error> {
         self.set_addressing_mode(AddressingMode::TenBit);
@@ -464,7 +469,7 @@ impl<USCI: EUsciI2CBus> Read<TenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> Write<SevenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> Write<SevenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
         self.set_addressing_mode(AddressingMode::SevenBit);
@@ -473,7 +478,7 @@ impl<USCI: EUsciI2CBus> Write<SevenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> Write<TenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> Write<TenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn write(&mut self, address: u16, bytes: &[u8]) -> Result<(), Self::Error> {
         self.set_addressing_mode(AddressingMode::TenBit);
@@ -482,7 +487,7 @@ impl<USCI: EUsciI2CBus> Write<TenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> WriteIter<SevenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> WriteIter<SevenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn write<B>(&mut self, address: u8, bytes: B) -> Result<(), Self::Error>
     where
@@ -494,7 +499,7 @@ impl<USCI: EUsciI2CBus> WriteIter<SevenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> WriteIter<TenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> WriteIter<TenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn write<B>(&mut self, address: u16, bytes: B) -> Result<(), Self::Error>
     where
@@ -506,7 +511,7 @@ impl<USCI: EUsciI2CBus> WriteIter<TenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> WriteRead<SevenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> WriteRead<SevenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn write_read(
         &mut self,
@@ -519,7 +524,7 @@ impl<USCI: EUsciI2CBus> WriteRead<SevenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> WriteRead<TenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> WriteRead<TenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn write_read(
         &mut self,
@@ -532,7 +537,7 @@ impl<USCI: EUsciI2CBus> WriteRead<TenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> Transactional<SevenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> Transactional<SevenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn exec(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
         self.set_addressing_mode(AddressingMode::SevenBit);
@@ -540,7 +545,7 @@ impl<USCI: EUsciI2CBus> Transactional<SevenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> Transactional<TenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> Transactional<TenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn exec(&mut self, address: u16, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
         self.set_addressing_mode(AddressingMode::TenBit);
@@ -548,7 +553,7 @@ impl<USCI: EUsciI2CBus> Transactional<TenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> TransactionalIter<SevenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> TransactionalIter<SevenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn exec_iter<'a, O>(&mut self, address: u8, operations: O) -> Result<(), Self::Error>
     where
@@ -559,7 +564,7 @@ impl<USCI: EUsciI2CBus> TransactionalIter<SevenBitAddress> for SDL<USCI> {
     }
 }
 
-impl<USCI: EUsciI2CBus> TransactionalIter<TenBitAddress> for SDL<USCI> {
+impl<USCI: EUsciI2CBus> TransactionalIter<TenBitAddress> for SDL<'_, USCI> {
     type Error = I2CErr;
     fn exec_iter<'a, O>(&mut self, address: u16, operations: O) -> Result<(), Self::Error>
     where
@@ -0,0 +1,134 @@
+//! Infrared transmitter
+//!
+//! The SYSCFG infrared logic modulates eUSCI_A0 TX with the TB0.1 PWM output in hardware, and
+//! drives the result out of the UCA0TXD pin. `IrTx` combines a configured `serial::Tx` with a PWM
+//! carrier, usually around 38 kHz with 1/3 duty cycle, so that remote-control codes can be sent
+//! without generating the carrier in software.
+//!
+//! The carrier is the TB0.1 channel of `PwmParts3`, taken before it's initialized with a pin. TB0.1
+//! only drives P1.6 when initialized, but P1.6 is also UCA0RXD, so leaving it uninitialized keeps
+//! the pin free for the UART receiver.
+//!
+//! Data can come from the UART, which suits IrDA-style links, or be keyed by software with
+//! `mark`/`space` and `send_pulses`, which suits pulse-distance codes like NEC and RC-5.
+
+use crate::hal::blocking::delay::DelayUs;
+use crate::hal::serial::Write;
+use crate::hw_traits::timerb::CCRn;
+use crate::hw_traits::Steal;
+use crate::pwm::PwmUninit;
+use crate::serial::Tx;
+use crate::timer::CCR1;
+use msp430fr2355 as pac;
+use pac::{E_USCI_A0, SYS, TB0};
+
+/// Polarity of the data that keys the carrier
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum IrPolarity {
+    /// The carrier is on while the data is 1
+    Normal,
+    /// The carrier is on while the data is 0, so an idle UART line leaves the carrier off
+    Inverted,
+}
+
+/// Infrared transmitter made of eUSCI_A0 TX and a TB0.1 carrier
+pub struct IrTx<'a> {
+    tx: Tx<'a, E_USCI_A0>,
+    carrier: PwmUninit<'a, TB0, CCR1>,
+    polarity: IrPolarity,
+}
+
+impl<'a> IrTx<'a> {
+    /// Turn on the infrared logic, with the data coming from the UART. The carrier is high for
+    /// `duty` cycles of every PWM period.
+    pub fn new(
+        tx: Tx<'a, E_USCI_A0>,
+        carrier: PwmUninit<'a, TB0, CCR1>,
+        duty: u16,
+        polarity: IrPolarity,
+    ) -> Self {
+        let timer = unsafe { TB0::steal() };
+        CCRn::<CCR1>::set_ccrn(&timer, duty);
+        let sys = unsafe { &*SYS::ptr() };
+        sys.syscfg1.modify(|_, w| {
+            w.irpsel()
+                .bit(polarity == IrPolarity::Inverted)
+                // ASK mode gates the carrier with the data
+                .irmsel()
+                .set_bit()
+                .irdssel()
+                .clear_bit()
+                .iren()
+                .set_bit()
+        });
+        IrTx {
+            tx,
+            carrier,
+            polarity,
+        }
+    }
+
+    /// Turn off the infrared logic and release the UART and carrier
+    pub fn free(self) -> (Tx<'a, E_USCI_A0>, PwmUninit<'a, TB0, CCR1>) {
+        let sys = unsafe { &*SYS::ptr() };
+        sys.syscfg1.modify(|_, w| w.iren().clear_bit());
+        (self.tx, self.carrier)
+    }
+
+    /// Key the carrier from the UART, which is the default
+    #[inline]
+    pub fn uart_data(&mut self) {
+        let sys = unsafe { &*SYS::ptr() };
+        sys.syscfg1.modify(|_, w| w.irdssel().clear_bit());
+    }
+
+    // Key the carrier from the IRDATA bit
+    #[inline]
+    fn set_data(&mut self, on: bool) {
+        let bit = on == (self.polarity == IrPolarity::Normal);
+        let sys = unsafe { &*SYS::ptr() };
+        sys.syscfg1
+            .modify(|_, w| w.irdata().bit(bit).irdssel().set_bit());
+    }
+
+    /// Turn the carrier on, keying it from software
+    #[inline]
+    pub fn mark(&mut self) {
+        self.set_data(true);
+    }
+
+    /// Turn the carrier off, keying it from software
+    #[inline]
+    pub fn space(&mut self) {
+        self.set_data(false);
+    }
+
+    /// Send alternating marks and spaces, starting with a mark, where each entry is a duration in
+    /// microseconds. The carrier is left off afterwards.
+    pub fn send_pulses<D: DelayUs<u32>>(&mut self, delay: &mut D, pulses: &[u32]) {
+        for (i, &us) in pulses.iter().enumerate() {
+            if i % 2 == 0 {
+                self.mark();
+            } else {
+                self.space();
+            }
+            delay.delay_us(us);
+        }
+        self.space();
+    }
+}
+
+impl Write<u8> for IrTx<'_> {
+    type Error = void::Void;
+
+    /// Writes a byte through the UART. Only modulates the carrier after `uart_data`.
+    #[inline]
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        self.tx.write(byte)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.tx.flush()
+    }
+}
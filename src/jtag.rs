@@ -0,0 +1,215 @@
+//! JTAG mailbox and JTAG lock
+//!
+//! The JTAG mailbox (JMB) exchanges 16-bit or 32-bit words with a debugger or programmer over
+//! JTAG or Spy-Bi-Wire, without halting the CPU. `JtagMailbox` is taken from `SysCfg`, starts in
+//! 16-bit mode, and can be switched to 32-bit mode, where both mailbox registers of each direction
+//! move together. The NMIs raised by the mailbox are enabled with
+//! `SysCfg::jtag_mailbox_interrupts`.
+//!
+//! The debug interface is locked by the JTAG signature words at 0xFF80 in main FRAM, which are
+//! checked at every BOR. `jtag_lock!` places a `JtagLock` there when the program is flashed,
+//! either locking JTAG for good or protecting it with a password. A lock can only be removed by a
+//! BSL mass erase, or by a programmer that knows the password.
+//!
+//! The signature sits outside the regions of a usual `memory.x`, so the linker script has to
+//! place the `.jtag_signature` section explicitly:
+//!
+//! ```text
+//! MEMORY
+//! {
+//!   /* ... */
+//!   JTAGSIG : ORIGIN = 0xFF80, LENGTH = 0x24
+//! }
+//!
+//! SECTIONS
+//! {
+//!   .jtag_signature : { KEEP(*(.jtag_signature)); } > JTAGSIG
+//! }
+//! ```
+//!
+//! The `memory.x` in this repository already does so. Without that section the signature isn't
+//! programmed, and JTAG stays open.
+
+use core::marker::PhantomData;
+use msp430fr2355 as pac;
+use pac::SYS;
+
+mod sealed {
+    pub trait SealedMailboxMode {}
+}
+
+/// Word size of the JTAG mailbox
+pub trait MailboxMode: sealed::SealedMailboxMode {}
+
+/// 16-bit transfers, using only the first mailbox register of each direction
+pub struct Mode16;
+/// 32-bit transfers, using both mailbox registers of each direction
+pub struct Mode32;
+
+impl sealed::SealedMailboxMode for Mode16 {}
+impl sealed::SealedMailboxMode for Mode32 {}
+impl MailboxMode for Mode16 {}
+impl MailboxMode for Mode32 {}
+
+/// JTAG mailbox
+pub struct JtagMailbox<MODE: MailboxMode = Mode16>(PhantomData<MODE>);
+
+impl JtagMailbox<Mode16> {
+    // Only handed out once, by `SysCfg`
+    #[inline]
+    pub(crate) fn new() -> Self {
+        // Keep any data the debugger has already sent
+        Self::sys().sysjmbc.modify(|_, w| w.jmbmode()._16bit());
+        JtagMailbox(PhantomData)
+    }
+
+    /// Switch to 32-bit transfers
+    #[inline]
+    pub fn into_32bit(self) -> JtagMailbox<Mode32> {
+        Self::sys().sysjmbc.modify(|_, w| w.jmbmode()._32bit());
+        JtagMailbox(PhantomData)
+    }
+
+    /// Read a word sent by the debugger, if one is waiting
+    #[inline]
+    pub fn read(&mut self) -> nb::Result<u16, void::Void> {
+        let sys = Self::sys();
+        if sys.sysjmbc.read().jmbin0fg().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        // Reading clears the input flag
+        Ok(sys.sysjmbi0.read().bits())
+    }
+
+    /// Write a word for the debugger, once it has read the previous one
+    #[inline]
+    pub fn write(&mut self, word: u16) -> nb::Result<(), void::Void> {
+        let sys = Self::sys();
+        if sys.sysjmbc.read().jmbout0fg().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        sys.sysjmbo0.write(|w| unsafe { w.bits(word) });
+        Ok(())
+    }
+}
+
+impl JtagMailbox<Mode32> {
+    /// Switch to 16-bit transfers
+    #[inline]
+    pub fn into_16bit(self) -> JtagMailbox<Mode16> {
+        Self::sys().sysjmbc.modify(|_, w| w.jmbmode()._16bit());
+        JtagMailbox(PhantomData)
+    }
+
+    /// Read a double word sent by the debugger, if one is waiting
+    #[inline]
+    pub fn read(&mut self) -> nb::Result<u32, void::Void> {
+        let sys = Self::sys();
+        if sys.sysjmbc.read().jmbin1fg().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        // Reading the high word last clears the input flag
+        let lo = sys.sysjmbi0.read().bits() as u32;
+        let hi = sys.sysjmbi1.read().bits() as u32;
+        Ok(hi << 16 | lo)
+    }
+
+    /// Write a double word for the debugger, once it has read the previous one
+    #[inline]
+    pub fn write(&mut self, word: u32) -> nb::Result<(), void::Void> {
+        let sys = Self::sys();
+        if sys.sysjmbc.read().jmbout1fg().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        sys.sysjmbo0.write(|w| unsafe { w.bits(word as u16) });
+        sys.sysjmbo1
+            .write(|w| unsafe { w.bits((word >> 16) as u16) });
+        Ok(())
+    }
+}
+
+impl<MODE: MailboxMode> JtagMailbox<MODE> {
+    #[inline(always)]
+    fn sys() -> &'static pac::sys::RegisterBlock {
+        unsafe { &*SYS::ptr() }
+    }
+
+    /// Whether the debugger has sent data that hasn't been read yet
+    #[inline]
+    pub fn input_pending(&self) -> bool {
+        let jmbc = Self::sys().sysjmbc.read();
+        jmbc.jmbin0fg().bit_is_set() || jmbc.jmbin1fg().bit_is_set()
+    }
+
+    /// Whether the debugger has read the last data written
+    #[inline]
+    pub fn output_empty(&self) -> bool {
+        let jmbc = Self::sys().sysjmbc.read();
+        jmbc.jmbout0fg().bit_is_set() && jmbc.jmbout1fg().bit_is_set()
+    }
+}
+
+// Signature values
+const SIGNATURE_LOCKED: u16 = 0x5555;
+const SIGNATURE_PASSWORD: u16 = 0xAAAA;
+// Erased FRAM, which leaves the BSL signatures at their defaults
+const ERASED: u16 = 0xFFFF;
+/// Longest JTAG password in words, which fills the space up to the interrupt vectors
+pub const MAX_PASSWORD_WORDS: usize = 14;
+
+/// Contents of the JTAG signature area at 0xFF80, to be placed with `jtag_lock!`
+#[repr(C)]
+pub struct JtagLock<const N: usize> {
+    signature: [u16; 2],
+    // The BSL signatures share the area, and are left erased
+    bsl_signature: [u16; 2],
+    password: [u16; N],
+}
+
+impl JtagLock<0> {
+    /// Lock JTAG permanently. Only a BSL mass erase can unlock the device again.
+    #[inline]
+    pub const fn locked() -> Self {
+        JtagLock {
+            signature: [SIGNATURE_LOCKED; 2],
+            bsl_signature: [ERASED; 2],
+            password: [],
+        }
+    }
+}
+
+impl<const N: usize> JtagLock<N> {
+    /// Protect JTAG with a password, which the programmer has to send before it can connect.
+    /// Fails to compile if the password is empty or longer than `MAX_PASSWORD_WORDS`.
+    #[inline]
+    pub const fn password(password: [u16; N]) -> Self {
+        assert!(N > 0 && N <= MAX_PASSWORD_WORDS);
+        JtagLock {
+            signature: [SIGNATURE_PASSWORD, N as u16],
+            bsl_signature: [ERASED; 2],
+            password,
+        }
+    }
+
+    /// Length of the password in words, which is 0 for a permanent lock
+    #[inline]
+    pub const fn password_len(&self) -> usize {
+        N
+    }
+}
+
+/// Program a `JtagLock` into the JTAG signature area when flashing, which requires the
+/// `.jtag_signature` section in the linker script. Can only be used once in a program.
+///
+/// ```ignore
+/// jtag_lock!(JtagLock::locked());
+/// ```
+#[macro_export]
+macro_rules! jtag_lock {
+    ($lock:expr) => {
+        #[link_section = ".jtag_signature"]
+        #[no_mangle]
+        #[used]
+        static __JTAG_LOCK: $crate::jtag::JtagLock<{ $lock.password_len() }> = $lock;
+    };
+}
@@ -0,0 +1,389 @@
+//! Power-fail-safe key/value store in FRAM
+//!
+//! The store keeps small records, addressed by a `u8` key, in a `Persistent` byte array. The
+//! array is split into two halves, only one of which is active at a time. Each half starts with a
+//! header holding a generation number, followed by a journal of records:
+//!
+//! | Field      | Size      |
+//! |------------|-----------|
+//! | generation | 2         |
+//! | key        | 1         |
+//! | length     | 1         |
+//! | value      | `length`  |
+//! | CRC        | 2         |
+//!
+//! A `put` appends a record and writes its CRC last, so a record cut short by a power loss fails
+//! its CRC and is ignored, leaving the previous record for that key as the latest one. When the
+//! active half is full, the latest record of every key is copied into the other half, whose
+//! header is written last to make it active. Until then, the old half stays active, so a power
+//! loss during the copy loses nothing.
+//!
+//! `KvStore::open` recovers the store at boot by picking the half with the newest valid header
+//! and scanning its journal up to the first invalid record.
+//!
+//! ```ignore
+//! #[link_section = ".persistent"]
+//! static STORE: Persistent<[u8; 256]> = Persistent::new([0; 256]);
+//!
+//! let mut store = KvStore::open(&STORE, &mut fram);
+//! let boots: u16 = store.get(0).unwrap_or(0);
+//! store.put(&mut fram, 0, &(boots + 1)).ok();
+//! ```
+
+use crate::crc::{crc16, crc16_update};
+use crate::fram::{Fram, Persistent};
+use core::ptr;
+
+// Marks an initialized half
+const MAGIC: u16 = 0x4B56;
+// Half header: magic, generation, and CRC
+const HEADER_LEN: usize = 6;
+// Record overhead: generation, key, length, and CRC
+const RECORD_OVERHEAD: usize = 6;
+
+/// Errors writing to the store
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreError {
+    /// The value is longer than 255 bytes
+    TooLong,
+    /// The latest records don't fit in one half of the store, even after compaction
+    Full,
+}
+
+/// Value that can be kept in a `KvStore`
+pub trait StoreValue: Sized {
+    /// Encoded length in bytes
+    const LEN: usize;
+    /// Encoded value, which is `LEN` bytes long
+    type Bytes: AsRef<[u8]>;
+
+    /// Encode the value
+    fn encode(&self) -> Self::Bytes;
+
+    /// Decode a value from `buf`, which is `LEN` bytes long. Returns `None` if `buf` isn't a
+    /// valid encoding.
+    fn decode(buf: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_store_value_int {
+    ($($t:ty),*) => {
+        $(
+            impl StoreValue for $t {
+                const LEN: usize = core::mem::size_of::<$t>();
+                type Bytes = [u8; core::mem::size_of::<$t>()];
+
+                #[inline]
+                fn encode(&self) -> Self::Bytes {
+                    self.to_le_bytes()
+                }
+
+                #[inline]
+                fn decode(buf: &[u8]) -> Option<Self> {
+                    let mut bytes = [0; core::mem::size_of::<$t>()];
+                    bytes.copy_from_slice(buf.get(..Self::LEN)?);
+                    Some(<$t>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_store_value_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl<const N: usize> StoreValue for [u8; N] {
+    const LEN: usize = N;
+    type Bytes = [u8; N];
+
+    #[inline]
+    fn encode(&self) -> Self::Bytes {
+        *self
+    }
+
+    #[inline]
+    fn decode(buf: &[u8]) -> Option<Self> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(buf.get(..N)?);
+        Some(bytes)
+    }
+}
+
+// Value of a record being written: either new data, or the value of an existing record at an
+// offset, which is copied without holding a reference into the array
+#[derive(Clone, Copy)]
+enum Data<'a> {
+    Slice(&'a [u8]),
+    Copy(usize, usize),
+}
+
+impl Data<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        match *self {
+            Data::Slice(d) => d.len(),
+            Data::Copy(_, len) => len,
+        }
+    }
+}
+
+// Location of a valid record in the active half
+#[derive(Clone, Copy)]
+struct Record {
+    key: u8,
+    // Offset of the value from the start of the array
+    value: usize,
+    len: usize,
+}
+
+/// Key/value store kept in a `Persistent` byte array, which must not be used for anything else
+pub struct KvStore<const N: usize> {
+    mem: &'static Persistent<[u8; N]>,
+    // Offset of the active half
+    active: usize,
+    generation: u16,
+    // Offset just past the last valid record
+    end: usize,
+}
+
+impl<const N: usize> KvStore<N> {
+    const HALF: usize = N / 2;
+    // Each half has to hold its header and at least one record, which fails the build otherwise
+    const FITS: () = assert!(
+        Self::HALF >= HEADER_LEN + RECORD_OVERHEAD,
+        "KvStore too small"
+    );
+
+    /// Recover the store from FRAM, or format it if neither half holds a valid header. Fails to
+    /// compile if `N` is too small to hold a header and a record in each half.
+    pub fn open(mem: &'static Persistent<[u8; N]>, fram: &mut Fram) -> Self {
+        let () = Self::FITS;
+        let mut store = KvStore {
+            mem,
+            active: 0,
+            generation: 0,
+            end: HEADER_LEN,
+        };
+        match (store.header(0), store.header(Self::HALF)) {
+            (Some(a), Some(b)) => {
+                // Generations wrap around, so compare them by distance
+                if (b.wrapping_sub(a) as i16) > 0 {
+                    store.active = Self::HALF;
+                    store.generation = b;
+                } else {
+                    store.generation = a;
+                }
+            }
+            (Some(a), None) => store.generation = a,
+            (None, Some(b)) => {
+                store.active = Self::HALF;
+                store.generation = b;
+            }
+            (None, None) => store.write_header(fram, 0, 0),
+        }
+        store.end = store.scan_end();
+        store
+    }
+
+    #[inline(always)]
+    fn bytes(&self) -> &[u8] {
+        unsafe { &*(self.mem.as_ptr() as *const [u8; N]) }
+    }
+
+    #[inline]
+    fn u16_at(&self, offset: usize) -> u16 {
+        let b = self.bytes();
+        u16::from_le_bytes([b[offset], b[offset + 1]])
+    }
+
+    // Generation of the half at `half`, if its header is valid
+    fn header(&self, half: usize) -> Option<u16> {
+        let header = &self.bytes()[half..half + HEADER_LEN];
+        let crc = u16::from_le_bytes([header[4], header[5]]);
+        if self.u16_at(half) == MAGIC && crc16(&header[..4]) == crc {
+            Some(self.u16_at(half + 2))
+        } else {
+            None
+        }
+    }
+
+    // Parse the record at `pos` in the half at `half`, as long as it belongs to `generation`
+    fn record_at(&self, half: usize, generation: u16, pos: usize) -> Option<Record> {
+        let limit = half + Self::HALF;
+        if pos + RECORD_OVERHEAD > limit {
+            return None;
+        }
+        let b = self.bytes();
+        let len = b[pos + 3] as usize;
+        let crc_pos = pos + 4 + len;
+        if crc_pos + 2 > limit || self.u16_at(pos) != generation {
+            return None;
+        }
+        if crc16(&b[pos..crc_pos]) != self.u16_at(crc_pos) {
+            return None;
+        }
+        Some(Record {
+            key: b[pos + 2],
+            value: pos + 4,
+            len,
+        })
+    }
+
+    // Offset just past the last valid record of the active half
+    fn scan_end(&self) -> usize {
+        let mut pos = self.active + HEADER_LEN;
+        while let Some(rec) = self.record_at(self.active, self.generation, pos) {
+            pos = rec.value + rec.len + 2;
+        }
+        pos
+    }
+
+    // Latest record for `key` in the active half
+    fn find(&self, key: u8) -> Option<Record> {
+        let mut pos = self.active + HEADER_LEN;
+        let mut found = None;
+        while pos < self.end {
+            let rec = match self.record_at(self.active, self.generation, pos) {
+                Some(rec) => rec,
+                None => break,
+            };
+            if rec.key == key {
+                found = Some(rec);
+            }
+            pos = rec.value + rec.len + 2;
+        }
+        found
+    }
+
+    /// Copy the latest value for `key` into `buf`, returning its length. Returns `None` if the
+    /// key has never been written or `buf` is too short.
+    pub fn get_raw(&self, key: u8, buf: &mut [u8]) -> Option<usize> {
+        let rec = self.find(key)?;
+        buf.get_mut(..rec.len)?
+            .copy_from_slice(&self.bytes()[rec.value..rec.value + rec.len]);
+        Some(rec.len)
+    }
+
+    /// Read the latest value for `key`. Returns `None` if the key has never been written, or if
+    /// its latest value has a different length than `T`.
+    pub fn get<T: StoreValue>(&self, key: u8) -> Option<T> {
+        let rec = self.find(key)?;
+        if rec.len != T::LEN {
+            return None;
+        }
+        T::decode(&self.bytes()[rec.value..rec.value + rec.len])
+    }
+
+    /// Write a new value for `key`
+    pub fn put<T: StoreValue>(
+        &mut self,
+        fram: &mut Fram,
+        key: u8,
+        val: &T,
+    ) -> Result<(), StoreError> {
+        self.put_raw(fram, key, val.encode().as_ref())
+    }
+
+    /// Write a new value for `key` as raw bytes
+    pub fn put_raw(&mut self, fram: &mut Fram, key: u8, data: &[u8]) -> Result<(), StoreError> {
+        if data.len() > u8::MAX as usize {
+            return Err(StoreError::TooLong);
+        }
+        if self.end + RECORD_OVERHEAD + data.len() <= self.active + Self::HALF {
+            self.end = self.write_record(fram, self.end, self.generation, key, Data::Slice(data));
+            Ok(())
+        } else {
+            self.compact(fram, key, data)
+        }
+    }
+
+    // Copy the latest record of every key other than `key` into the inactive half, append the
+    // new record, and then switch halves by writing the new header
+    fn compact(&mut self, fram: &mut Fram, key: u8, data: &[u8]) -> Result<(), StoreError> {
+        let target = Self::HALF - self.active;
+        let generation = self.generation.wrapping_add(1);
+        let limit = target + Self::HALF;
+        let mut out = target + HEADER_LEN;
+
+        let mut pos = self.active + HEADER_LEN;
+        while pos < self.end {
+            let rec = match self.record_at(self.active, self.generation, pos) {
+                Some(rec) => rec,
+                None => break,
+            };
+            pos = rec.value + rec.len + 2;
+            let latest = self.find(rec.key).map(|r| r.value) == Some(rec.value);
+            if rec.key != key && latest {
+                if out + RECORD_OVERHEAD + rec.len > limit {
+                    return Err(StoreError::Full);
+                }
+                out = self.write_record(
+                    fram,
+                    out,
+                    generation,
+                    rec.key,
+                    Data::Copy(rec.value, rec.len),
+                );
+            }
+        }
+        if out + RECORD_OVERHEAD + data.len() > limit {
+            return Err(StoreError::Full);
+        }
+        out = self.write_record(fram, out, generation, key, Data::Slice(data));
+
+        self.write_header(fram, target, generation);
+        self.active = target;
+        self.generation = generation;
+        self.end = out;
+        Ok(())
+    }
+
+    // Write a record with its CRC last, returning the offset just past it. Each byte is written
+    // with a volatile write so that they land in order.
+    fn write_record(
+        &self,
+        fram: &mut Fram,
+        pos: usize,
+        generation: u16,
+        key: u8,
+        data: Data,
+    ) -> usize {
+        let len = data.len();
+        let gen = generation.to_le_bytes();
+        let head = [gen[0], gen[1], key, len as u8];
+        let crc = match data {
+            Data::Slice(d) => crc16_update(crc16(&head), d),
+            Data::Copy(src, len) => crc16_update(crc16(&head), &self.bytes()[src..src + len]),
+        };
+
+        let _guard = fram.unlock_program();
+        let base = self.mem.as_ptr() as *mut u8;
+        let write = |offset: usize, b: u8| unsafe { ptr::write_volatile(base.add(offset), b) };
+        for (i, &b) in head.iter().enumerate() {
+            write(pos + i, b);
+        }
+        for i in 0..len {
+            let b = match data {
+                Data::Slice(d) => d[i],
+                Data::Copy(src, _) => unsafe { ptr::read_volatile(base.add(src + i)) },
+            };
+            write(pos + 4 + i, b);
+        }
+        for (i, &b) in crc.to_le_bytes().iter().enumerate() {
+            write(pos + 4 + len + i, b);
+        }
+        pos + RECORD_OVERHEAD + len
+    }
+
+    // Write a half header with its CRC last
+    fn write_header(&self, fram: &mut Fram, half: usize, generation: u16) {
+        let magic = MAGIC.to_le_bytes();
+        let gen = generation.to_le_bytes();
+        let head = [magic[0], magic[1], gen[0], gen[1]];
+        let crc = crc16(&head).to_le_bytes();
+
+        let _guard = fram.unlock_program();
+        let base = self.mem.as_ptr() as *mut u8;
+        for (i, &b) in head.iter().chain(crc.iter()).enumerate() {
+            unsafe { ptr::write_volatile(base.add(half + i), b) };
+        }
+    }
+}
@@ -29,17 +29,24 @@
 #![feature(asm_const)]
 
 pub mod adc;
+pub mod bakmem;
 pub mod batch_gpio;
 pub mod capture;
 pub mod clock;
+pub mod crc;
 pub mod fram;
 pub mod gpio;
+pub mod jtag;
+pub mod kvstore;
 pub mod pmm;
 pub mod prelude;
 pub mod pwm;
 pub mod rtc;
 pub mod serial;
+pub mod syscfg;
+pub mod time;
 pub mod timer;
+pub mod tlv;
 pub mod watchdog;
 
 mod hw_traits;
@@ -47,6 +54,7 @@ mod util;
 
 pub mod delay;
 pub mod i2c;
+pub mod ir;
 pub mod spi;
 
 pub use embedded_hal as hal;
@@ -1,14 +1,610 @@
 //! Power management module
-
-use msp430fr2355::PMM;
+//!
+//! Besides unlocking the GPIOs, `Pmm` puts the CPU into one of the low power modes (LPMs). The
+//! CPU, MCLK, and FLL stop in every LPM, while the other clocks behave as follows:
+//!
+//! | Mode | SMCLK                    | ACLK | FLL |
+//! |------|--------------------------|------|-----|
+//! | LPM0 | on                       | on   | on  |
+//! | LPM1 | on                       | on   | off |
+//! | LPM3 | off, unless requested    | on   | off |
+//! | LPM4 | off                      | off  | off |
+//!
+//! To catch peripherals that would silently stop while asleep, LPM entry takes exclusive references
+//! to the clock objects of every clock the chosen mode stops. Peripherals borrow the clock object
+//! they were configured from, so entering such a mode while one of them still exists doesn't
+//! compile. LPM3 and LPM4 take `&mut Smclk`, or `&Mclk<SmclkDisabled>` if SMCLK is disabled. Since
+//! peripherals keep SMCLK running in LPM3 through conditional requests, LPM3 also accepts a shared
+//! `&Smclk` while its requests are enabled. LPM4 additionally takes `&mut Aclk`.
+//!
+//! Interrupts are always enabled when entering an LPM, since only an interrupt can wake the CPU.
+//! By default the CPU goes straight back to sleep once the interrupt handler returns. To keep the
+//! CPU awake and return from `enter_lpm`, declare the handler with `#[interrupt(wake_cpu)]`, which
+//! clears the LPM bits of the status register saved on the stack.
+//!
+//! LPM3.5 and LPM4.5 turn off the core voltage regulator, so all RAM and register contents are
+//! lost and waking up resets the device. Only the RTC (in LPM3.5) and port interrupts can wake it.
+//! The GPIO state is held by LOCKLPM5 across the shutdown, so after waking up, create a
+//! `LockedPmm` with `Pmm::new_locked`, restore the GPIO configuration with `Batch::split_locked`,
+//! and then call `LockedPmm::unlock`. The interrupt that caused the wakeup is serviced once
+//! interrupts are enabled, as long as it was re-enabled before unlocking. State that must survive
+//! LPM3.5 can be kept in the backup memory from `Pmm::take_bakmem`.
+//!
+//! Creating a `Pmm` or `LockedPmm` also drains SYSRSTIV into a `ResetCauses` set, so the reason
+//! for the last reset is available through `reset_causes`. Nothing else should read SYSRSTIV
+//! beforehand, since each read clears the cause it returns.
+//!
+//! The high-side supply voltage supervisor (SVSH) always runs in active mode, LPM0, and LPM1. By
+//! default it also runs in LPM3, LPM4, and LPMx.5, resetting the device if the supply drops too
+//! low. The `SVS` typestate of `Pmm` tracks this, and `svs_off_in_lpm` turns it off in those modes
+//! to save current at the cost of brownout protection while asleep.
+//!
+//! The internal 1.5/2.0/2.5 V reference lives in the PMM too. `Pmm::take_refgen` hands out the
+//! reference generator once, and enabling it yields a `Reference` token that analog peripherals
+//! such as the ADC take to select the internal reference and convert results into millivolts.
+use crate::bakmem::BakMem;
+use crate::clock::{Aclk, Mclk, ReqEnabled, Smclk, SmclkDisabled};
+use core::arch::asm;
+use core::marker::PhantomData;
+use core::ptr;
+use msp430fr2355 as pac;
+use pac::{PMM, SYS};
 
 /// PMM type
-pub struct Pmm(());
+pub struct Pmm<SVS = SvsOn> {
+    reset_causes: ResetCauses,
+    refgen: Option<RefGen>,
+    bakmem: Option<BakMem>,
+    _svs: PhantomData<SVS>,
+}
+
+/// Typestate for a `Pmm` whose SVSH keeps running in LPM3, LPM4, and LPMx.5
+pub struct SvsOn;
+/// Typestate for a `Pmm` whose SVSH is off in LPM3, LPM4, and LPMx.5
+pub struct SvsOff;
 
 impl Pmm {
     /// Sets the LOCKLPM5 bit and returns a `Pmm`
     pub fn new(pmm: PMM) -> Pmm {
+        let reset_causes = ResetCauses::read();
+        pmm.pm5ctl0.write(|w| w.locklpm5().locklpm5_0());
+        Pmm::from_causes(reset_causes)
+    }
+
+    // SVSHE is only reset by a BOR, so set it explicitly to match the typestate
+    #[inline(always)]
+    fn from_causes(reset_causes: ResetCauses) -> Pmm {
+        modify_pmmctl0_lo(SVSHE, 0);
+        Pmm {
+            reset_causes,
+            refgen: Some(RefGen(())),
+            bakmem: Some(BakMem::new()),
+            _svs: PhantomData,
+        }
+    }
+
+    /// Returns a `LockedPmm` without clearing LOCKLPM5, so that the GPIOs can be reconfigured to
+    /// their pre-sleep state after waking from LPM3.5 or LPM4.5
+    pub fn new_locked(_pmm: PMM) -> LockedPmm {
+        let pmm = unsafe { &*PMM::ptr() };
+        LockedPmm {
+            lpm5_wakeup: pmm.pmmifg.read().pmmlpm5ifg().bit(),
+            reset_causes: ResetCauses::read(),
+        }
+    }
+
+    /// Turns off the SVSH in LPM3, LPM4, and LPMx.5, saving current while asleep. Supply drops
+    /// during those modes then go undetected until the CPU wakes up.
+    #[inline]
+    pub fn svs_off_in_lpm(self) -> Pmm<SvsOff> {
+        modify_pmmctl0_lo(0, SVSHE);
+        Pmm {
+            reset_causes: self.reset_causes,
+            refgen: self.refgen,
+            bakmem: self.bakmem,
+            _svs: PhantomData,
+        }
+    }
+}
+
+impl Pmm<SvsOff> {
+    /// Keeps the SVSH running in LPM3, LPM4, and LPMx.5
+    #[inline]
+    pub fn svs_on_in_lpm(self) -> Pmm {
+        modify_pmmctl0_lo(SVSHE, 0);
+        Pmm {
+            reset_causes: self.reset_causes,
+            refgen: self.refgen,
+            bakmem: self.bakmem,
+            _svs: PhantomData,
+        }
+    }
+}
+
+impl<SVS> Pmm<SVS> {
+    /// Causes of the last reset, read from SYSRSTIV when the `Pmm` was created
+    #[inline]
+    pub fn reset_causes(&self) -> ResetCauses {
+        self.reset_causes
+    }
+
+    /// Enables interrupts and enters a low power mode, returning once an interrupt handler
+    /// declared with `#[interrupt(wake_cpu)]` has run. `clocks` holds exclusive references to the
+    /// clocks that `MODE` stops, as listed by the implementations of `SleepClocks`.
+    #[inline]
+    pub fn enter_lpm<MODE: LowPowerMode, C: SleepClocks<MODE>>(&mut self, _mode: MODE, _clocks: C) {
+        enter_lpm_bits::<MODE>();
+    }
+
+    /// Shuts down into LPM3.5, from which the device can only be woken by the RTC or a port
+    /// interrupt. The RTC must be clocked from XT1 or VLO to keep running.
+    #[inline]
+    pub fn enter_lpm3_5(self) -> ! {
+        regulator_off();
+        loop {
+            enter_lpm_bits::<Lpm3>();
+        }
+    }
+
+    /// Shuts down into LPM4.5, from which the device can only be woken by a port interrupt
+    #[inline]
+    pub fn enter_lpm4_5(self) -> ! {
+        regulator_off();
+        loop {
+            enter_lpm_bits::<Lpm4>();
+        }
+    }
+
+    /// Takes the internal reference generator, which can only be done once
+    #[inline]
+    pub fn take_refgen(&mut self) -> Option<RefGen> {
+        self.refgen.take()
+    }
+
+    /// Takes the backup memory, which can only be done once
+    #[inline]
+    pub fn take_bakmem(&mut self) -> Option<BakMem> {
+        self.bakmem.take()
+    }
+
+    /// Reads the PMM interrupt flags
+    #[inline]
+    pub fn flags(&self) -> PmmFlags {
+        let pmm = unsafe { &*PMM::ptr() };
+        PmmFlags(pmm.pmmifg.read().bits())
+    }
+
+    /// Clears all PMM interrupt flags
+    #[inline]
+    pub fn clear_flags(&mut self) {
+        with_unlocked(|pmm| pmm.pmmifg.write(|w| unsafe { w.bits(0) }));
+    }
+}
+
+/// PMM interrupt flags, which record supply events independently of SYSRSTIV
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PmmFlags(u16);
+
+impl PmmFlags {
+    /// A brownout reset has occurred
+    #[inline]
+    pub fn brownout(&self) -> bool {
+        self.0 & (1 << 8) != 0
+    }
+
+    /// A reset from the RST pin has occurred
+    #[inline]
+    pub fn reset_pin(&self) -> bool {
+        self.0 & (1 << 9) != 0
+    }
+
+    /// A software POR has occurred
+    #[inline]
+    pub fn software_por(&self) -> bool {
+        self.0 & (1 << 10) != 0
+    }
+
+    /// The SVSH has detected a supply drop
+    #[inline]
+    pub fn svsh(&self) -> bool {
+        self.0 & (1 << 13) != 0
+    }
+
+    /// The device has woken up from LPM3.5 or LPM4.5
+    #[inline]
+    pub fn lpm5_wakeup(&self) -> bool {
+        self.0 & (1 << 15) != 0
+    }
+}
+
+/// Internal shared reference voltage
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RefVoltage {
+    /// 1.5 V
+    _1V5,
+    /// 2.0 V
+    _2V0,
+    /// 2.5 V
+    _2V5,
+}
+
+impl RefVoltage {
+    /// Nominal reference voltage in millivolts
+    #[inline]
+    pub const fn millivolts(self) -> u16 {
+        match self {
+            RefVoltage::_1V5 => 1500,
+            RefVoltage::_2V0 => 2000,
+            RefVoltage::_2V5 => 2500,
+        }
+    }
+
+    #[inline]
+    fn refvsel(self) -> u8 {
+        match self {
+            RefVoltage::_1V5 => 0b00,
+            RefVoltage::_2V0 => 0b01,
+            RefVoltage::_2V5 => 0b10,
+        }
+    }
+}
+
+/// Disabled internal reference generator (REFGEN), shared by the ADC, SAC DAC, and eCOMP
+pub struct RefGen(());
+
+impl RefGen {
+    /// Turns on the internal reference at the given voltage. The reference takes some time to
+    /// settle, so check `Reference::is_ready` before relying on it.
+    #[inline]
+    pub fn enable(self, voltage: RefVoltage) -> Reference {
+        let refvsel = voltage.refvsel();
+        with_unlocked(|pmm| {
+            pmm.pmmctl2
+                .modify(|_, w| w.refvsel().bits(refvsel).intrefen().set_bit())
+        });
+        Reference { voltage }
+    }
+}
+
+/// Enabled internal reference, which analog peripherals accept as proof that the reference is
+/// running at a known voltage
+pub struct Reference {
+    voltage: RefVoltage,
+}
+
+impl Reference {
+    /// Whether the reference has settled
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        let pmm = unsafe { &*PMM::ptr() };
+        pmm.pmmctl2.read().refgenrdy().bit()
+    }
+
+    /// Blocks until the reference has settled
+    #[inline]
+    pub fn wait_ready(&self) {
+        while !self.is_ready() {}
+    }
+
+    /// Selected reference voltage
+    #[inline]
+    pub fn voltage(&self) -> RefVoltage {
+        self.voltage
+    }
+
+    /// Nominal reference voltage in millivolts
+    #[inline]
+    pub fn millivolts(&self) -> u16 {
+        self.voltage.millivolts()
+    }
+
+    /// Turns off the internal reference
+    #[inline]
+    pub fn disable(self) -> RefGen {
+        with_unlocked(|pmm| unsafe { pmm.pmmctl2.clear_bits(|w| w.intrefen().clear_bit()) });
+        RefGen(())
+    }
+}
+
+// Turn the internal temperature sensor on or off
+#[inline]
+pub(crate) fn set_temp_sensor(enable: bool) {
+    with_unlocked(|pmm| pmm.pmmctl2.modify(|_, w| w.tsensoren().bit(enable)));
+}
+
+/// PMM before LOCKLPM5 has been cleared, while the GPIOs still hold their state from before a
+/// LPM3.5 or LPM4.5 shutdown
+pub struct LockedPmm {
+    lpm5_wakeup: bool,
+    reset_causes: ResetCauses,
+}
+
+impl LockedPmm {
+    /// Whether the device is waking up from LPM3.5 or LPM4.5, rather than starting from a reset
+    #[inline]
+    pub fn woke_from_lpm5(&self) -> bool {
+        self.lpm5_wakeup
+    }
+
+    /// Causes of the last reset, read from SYSRSTIV when the `LockedPmm` was created
+    #[inline]
+    pub fn reset_causes(&self) -> ResetCauses {
+        self.reset_causes
+    }
+
+    /// Clears the LPMx.5 wakeup flag and LOCKLPM5, applying the current GPIO configuration to the
+    /// pins. Any port or RTC interrupt flag that caused the wakeup stays pending until interrupts
+    /// are enabled.
+    pub fn unlock(self) -> Pmm {
+        with_unlocked(|pmm| unsafe { pmm.pmmifg.clear_bits(|w| w.pmmlpm5ifg().clear_bit()) });
+        let pmm = unsafe { &*PMM::ptr() };
         pmm.pm5ctl0.write(|w| w.locklpm5().locklpm5_0());
-        Pmm(())
+        Pmm::from_causes(self.reset_causes)
+    }
+}
+
+/// Source of a reset, as reported by SYSRSTIV
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetCause {
+    /// Brownout, including power-up
+    Brownout,
+    /// RST pin
+    ResetPin,
+    /// Software BOR, triggered by `software_bor`
+    SoftwareBor,
+    /// Wakeup from LPM3.5 or LPM4.5
+    Lpm5Wakeup,
+    /// Security violation
+    SecurityViolation,
+    /// High-side supply voltage supervisor event
+    Svsh,
+    /// Software POR, triggered by `software_por`
+    SoftwarePor,
+    /// Watchdog timeout
+    WatchdogTimeout,
+    /// Watchdog password violation
+    WatchdogPassword,
+    /// FRAM controller password violation
+    FramPassword,
+    /// Uncorrectable FRAM bit error
+    FramUncorrectable,
+    /// Instruction fetch from the peripheral area
+    PeripheralAreaFetch,
+    /// PMM password violation
+    PmmPassword,
+    /// FLL unlock
+    FllUnlock,
+}
+
+impl ResetCause {
+    // All causes, in order of SYSRSTIV priority
+    const ALL: [ResetCause; 14] = [
+        ResetCause::Brownout,
+        ResetCause::ResetPin,
+        ResetCause::SoftwareBor,
+        ResetCause::Lpm5Wakeup,
+        ResetCause::SecurityViolation,
+        ResetCause::Svsh,
+        ResetCause::SoftwarePor,
+        ResetCause::WatchdogTimeout,
+        ResetCause::WatchdogPassword,
+        ResetCause::FramPassword,
+        ResetCause::FramUncorrectable,
+        ResetCause::PeripheralAreaFetch,
+        ResetCause::PmmPassword,
+        ResetCause::FllUnlock,
+    ];
+
+    #[inline]
+    fn from_iv(iv: u16) -> Option<Self> {
+        match iv {
+            0x02 => Some(ResetCause::Brownout),
+            0x04 => Some(ResetCause::ResetPin),
+            0x06 => Some(ResetCause::SoftwareBor),
+            0x08 => Some(ResetCause::Lpm5Wakeup),
+            0x0A => Some(ResetCause::SecurityViolation),
+            0x0E => Some(ResetCause::Svsh),
+            0x14 => Some(ResetCause::SoftwarePor),
+            0x16 => Some(ResetCause::WatchdogTimeout),
+            0x18 => Some(ResetCause::WatchdogPassword),
+            0x1A => Some(ResetCause::FramPassword),
+            0x1C => Some(ResetCause::FramUncorrectable),
+            0x1E => Some(ResetCause::PeripheralAreaFetch),
+            0x20 => Some(ResetCause::PmmPassword),
+            0x24 => Some(ResetCause::FllUnlock),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn mask(self) -> u16 {
+        1 << self as u8
+    }
+}
+
+/// Set of reset causes pending in SYSRSTIV. A single reset can latch several causes, for example
+/// a brownout also flags the RST pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResetCauses(u16);
+
+impl ResetCauses {
+    // Read SYSRSTIV until it's empty. Each read returns and clears the highest priority cause.
+    fn read() -> Self {
+        let sys = unsafe { &*SYS::ptr() };
+        let mut mask = 0;
+        // SYSRSTIV has fewer than 32 sources, so this bounds the loop even if it never reads 0
+        for _ in 0..32 {
+            match sys.sysrstiv.read().bits() {
+                0 => break,
+                iv => {
+                    if let Some(cause) = ResetCause::from_iv(iv) {
+                        mask |= cause.mask();
+                    }
+                }
+            }
+        }
+        ResetCauses(mask)
+    }
+
+    /// Whether `cause` was latched
+    #[inline]
+    pub fn contains(&self, cause: ResetCause) -> bool {
+        self.0 & cause.mask() != 0
+    }
+
+    /// The highest priority cause, which is the most direct reason for the reset
+    #[inline]
+    pub fn primary(&self) -> Option<ResetCause> {
+        self.iter().next()
+    }
+
+    /// Iterate over the causes from highest to lowest priority
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = ResetCause> {
+        let causes = *self;
+        ResetCause::ALL
+            .iter()
+            .copied()
+            .filter(move |&cause| causes.contains(cause))
+    }
+}
+
+/// Triggers a software brownout reset, which resets the whole device
+pub fn software_bor() -> ! {
+    let pmm = unsafe { &*PMM::ptr() };
+    pmm.pmmctl0
+        .write(|w| unsafe { w.pmmpw().bits(PASSWORD) }.pmmswbor().pmmswbor_1());
+    // The reset takes effect within a few cycles
+    loop {
+        msp430::asm::nop();
+    }
+}
+
+/// Triggers a software power-on reset, which resets the device without resetting the PMM
+pub fn software_por() -> ! {
+    let pmm = unsafe { &*PMM::ptr() };
+    pmm.pmmctl0
+        .write(|w| unsafe { w.pmmpw().bits(PASSWORD) }.pmmswpor().pmmswpor_1());
+    // The reset takes effect within a few cycles
+    loop {
+        msp430::asm::nop();
     }
 }
+
+const PASSWORD: u8 = 0xA5;
+// PMMREGOFF and SVSHE bits in the low byte of PMMCTL0
+const PMMREGOFF: u8 = 0x10;
+const SVSHE: u8 = 0x40;
+
+// Byte pointers to PMMCTL0. The PMM registers are locked again by writing to the password byte
+// alone, since a word write carrying anything other than the password triggers a reset.
+#[inline(always)]
+fn pmmctl0_bytes(pmm: &pac::pmm::RegisterBlock) -> (*mut u8, *mut u8) {
+    let lo = pmm.pmmctl0.as_ptr() as *mut u8;
+    (lo, unsafe { lo.add(1) })
+}
+
+// Run `f` with the PMM registers unlocked
+#[inline(always)]
+fn with_unlocked<R>(f: impl FnOnce(&pac::pmm::RegisterBlock) -> R) -> R {
+    let pmm = unsafe { &*PMM::ptr() };
+    let (_, pw) = pmmctl0_bytes(pmm);
+    unsafe { ptr::write_volatile(pw, PASSWORD) };
+    let res = f(pmm);
+    unsafe { ptr::write_volatile(pw, 0) };
+    res
+}
+
+// Set and clear bits in the low byte of PMMCTL0
+#[inline(always)]
+fn modify_pmmctl0_lo(set: u8, clear: u8) {
+    with_unlocked(|pmm| {
+        let (lo, _) = pmmctl0_bytes(pmm);
+        unsafe { ptr::write_volatile(lo, (ptr::read_volatile(lo) & !clear) | set) };
+    });
+}
+
+// Turn off the core voltage regulator, so the next LPM3 or LPM4 entry becomes LPM3.5 or LPM4.5
+#[inline(always)]
+fn regulator_off() {
+    modify_pmmctl0_lo(PMMREGOFF, 0);
+}
+
+// SR bit that enables maskable interrupts
+const GIE: u16 = 0x08;
+// SR bits that turn off the CPU, FLL, DCO, and all oscillators respectively
+const CPUOFF: u16 = 0x10;
+const OSCOFF: u16 = 0x20;
+const SCG0: u16 = 0x40;
+const SCG1: u16 = 0x80;
+
+// Enable interrupts and enter the LPM in a single instruction, so a pending interrupt can't be
+// taken before the CPU is asleep. The user's guide requires a `nop` on both sides of an
+// instruction that sets GIE.
+#[inline(always)]
+pub(crate) fn enter_lpm_bits<MODE: LowPowerMode>() {
+    unsafe {
+        asm!(
+            "nop",
+            "bis #{bits}, SR",
+            "nop",
+            bits = const MODE::SR_BITS | GIE,
+            options(nostack)
+        )
+    };
+}
+
+mod sealed {
+    pub trait SealedLowPowerMode {}
+}
+
+/// A low power mode that can be entered from active mode
+pub trait LowPowerMode: sealed::SealedLowPowerMode {
+    #[doc(hidden)]
+    const SR_BITS: u16;
+}
+
+/// LPM0: CPU and MCLK off
+pub struct Lpm0;
+/// LPM1: CPU, MCLK, and FLL off
+pub struct Lpm1;
+/// LPM3: CPU, MCLK, FLL, and SMCLK off
+pub struct Lpm3;
+/// LPM4: all clocks off
+pub struct Lpm4;
+
+impl sealed::SealedLowPowerMode for Lpm0 {}
+impl sealed::SealedLowPowerMode for Lpm1 {}
+impl sealed::SealedLowPowerMode for Lpm3 {}
+impl sealed::SealedLowPowerMode for Lpm4 {}
+
+impl LowPowerMode for Lpm0 {
+    const SR_BITS: u16 = CPUOFF;
+}
+impl LowPowerMode for Lpm1 {
+    const SR_BITS: u16 = CPUOFF | SCG0;
+}
+impl LowPowerMode for Lpm3 {
+    const SR_BITS: u16 = CPUOFF | SCG0 | SCG1;
+}
+impl LowPowerMode for Lpm4 {
+    const SR_BITS: u16 = CPUOFF | SCG0 | SCG1 | OSCOFF;
+}
+
+/// Access to the clocks that low power mode `MODE` stops, proving that no peripheral still uses
+/// them
+///
+/// LPM0 and LPM1 stop neither SMCLK nor ACLK, so they take `()`. LPM3 takes `&mut Smclk`,
+/// `&Smclk` with conditional requests enabled, or `&Mclk<SmclkDisabled>`. LPM4 takes a tuple of
+/// the same SMCLK proof, except for the shared `&Smclk`, and `&mut Aclk`.
+pub trait SleepClocks<MODE: LowPowerMode> {}
+
+impl SleepClocks<Lpm0> for () {}
+impl SleepClocks<Lpm1> for () {}
+
+impl<REQ> SleepClocks<Lpm3> for &mut Smclk<REQ> {}
+impl SleepClocks<Lpm3> for &Smclk<ReqEnabled> {}
+impl SleepClocks<Lpm3> for &Mclk<SmclkDisabled> {}
+
+impl<SREQ, AREQ> SleepClocks<Lpm4> for (&mut Smclk<SREQ>, &mut Aclk<AREQ>) {}
+impl<AREQ> SleepClocks<Lpm4> for (&Mclk<SmclkDisabled>, &mut Aclk<AREQ>) {}
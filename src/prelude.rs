@@ -3,7 +3,10 @@
 pub use crate::capture::CapturePeriph as _msp430fr2x5x_hal_CapturePeriph;
 pub use crate::capture::CapturePin as _msp430fr2x5x_hal_CapturePin;
 pub use crate::clock::Clock as _msp430fr2x5x_hal_Clock;
+pub use crate::clock::FrozenClocks as _msp430fr2x5x_hal_FrozenClocks;
 pub use crate::clock::SmclkState as _msp430fr2x5x_hal_SmclkState;
+pub use crate::clock::Xt1Enabled as _msp430fr2x5x_hal_Xt1Enabled;
+pub use crate::clock::Xt1State as _msp430fr2x5x_hal_Xt1State;
 pub use crate::gpio::Alternate1 as _msp430fr2x5x_hal_Alternate1;
 pub use crate::gpio::Alternate2 as _msp430fr2x5x_hal_Alternate2;
 pub use crate::gpio::Alternate3 as _msp430fr2x5x_hal_Alternate3;
@@ -12,6 +15,8 @@ pub use crate::gpio::PinNum as _msp430fr2x5x_hal_PinNum;
 pub use crate::pwm::PwmPeriph as _msp430fr2x5x_hal_PwmPeriph;
 pub use crate::rtc::RtcClockSrc as _msp430fr2x5x_hal_RtcClockSrc;
 pub use crate::serial::SerialUsci as _msp430fr2x5x_hal_SerialUsci;
+pub use crate::time::ExtU32 as _msp430fr2x5x_hal_ExtU32;
+pub use crate::time::RateExtU32 as _msp430fr2x5x_hal_RateExtU32;
 pub use crate::timer::CapCmp as _msp430fr2x5x_hal_CapCmp;
 pub use crate::timer::CapCmpTimer3 as _msp430fr2x5x_hal_CapCmpTimer3;
 pub use crate::timer::CapCmpTimer7 as _msp430fr2x5x_hal_CapCmpTimer7;
@@ -1,7 +1,9 @@
 //! PWM ports
 //!
 //! Configures the board's TimerB peripherals into PWM ports. Each PWM port consists of multiple PWM
-//! pins which all share the same period but have their own duty cycles.
+//! pins which all share the same period but have their own duty cycles. The period is set by
+//! passing the desired PWM frequency, which is converted into timer cycles using the frequency of
+//! the timer clock.
 //!
 //! Each PWM pin starts off in an "uninitialized" state and must be initialized by passing in the
 //! appropriate alternate-function GPIO pin. Only initialized pins can be used for PWM.
@@ -11,6 +13,7 @@ use crate::gpio::{
     Pin6, Pin7, P1, P2, P5, P6,
 };
 use crate::hw_traits::timerb::{CCRn, Outmod};
+use crate::time::{self, Hertz, PeriodError};
 use crate::timer::{CapCmpTimer3, CapCmpTimer7};
 use core::marker::PhantomData;
 use embedded_hal::PwmPin;
@@ -104,56 +107,85 @@ impl PwmPeriph<CCR6> for pac::TB3 {
     const ALT: Alt = Alt::Alt1;
 }
 
-fn setup_pwm<T: TimerPeriph>(timer: &T, config: TimerConfig<T>, period: u16) {
+fn setup_pwm<T: TimerPeriph>(
+    timer: &T,
+    config: TimerConfig<'_, T>,
+    freq: Hertz,
+) -> Result<(), PeriodError> {
+    // Number of timer cycles per PWM period, rounded to the nearest cycle
+    let clk = config.freq().raw() as u64;
+    let freq = freq.raw().max(1) as u64;
+    let cycles = ((clk + freq / 2) / freq) as u32;
+    // The timer counts from 0 to CCR0 inclusive, so the period is one cycle longer than CCR0
+    let period = time::counter_threshold(cycles)?;
     config.write_regs(timer);
     CCRn::<CCR0>::set_ccrn(timer, period);
     CCRn::<CCR0>::config_outmod(timer, Outmod::Toggle);
+    Ok(())
 }
 
 /// Collection of uninitialized PWM pins derived from timer peripheral with 3 capture-compare registers
-pub struct PwmParts3<T: CapCmpTimer3> {
+pub struct PwmParts3<'a, T: CapCmpTimer3> {
     /// PWM pin 1 (derived from capture-compare register 1)
-    pub pwm1: PwmUninit<T, CCR1>,
+    pub pwm1: PwmUninit<'a, T, CCR1>,
     /// PWM pin 2 (derived from capture-compare register 2)
-    pub pwm2: PwmUninit<T, CCR2>,
+    pub pwm2: PwmUninit<'a, T, CCR2>,
 }
 
-impl<T: CapCmpTimer3> PwmParts3<T> {
-    /// Create uninitialized PWM pins with the same period
-    pub fn new(timer: T, config: TimerConfig<T>, period: u16) -> Self {
-        setup_pwm(&timer, config, period);
+impl<'a, T: CapCmpTimer3> PwmParts3<'a, T> {
+    /// Create uninitialized PWM pins with the same period, running at the PWM frequency `freq`.
+    /// Panics if the period is longer than 2^16 cycles of the timer clock, see `try_new` for
+    /// handling that.
+    pub fn new(timer: T, config: TimerConfig<'a, T>, freq: Hertz) -> Self {
+        Self::try_new(timer, config, freq).unwrap()
+    }
+
+    /// Create uninitialized PWM pins with the same period, running at the PWM frequency `freq`, or
+    /// return an error without touching the timer if the period is longer than 2^16 cycles of the
+    /// timer clock
+    pub fn try_new(timer: T, config: TimerConfig<'a, T>, freq: Hertz) -> Result<Self, PeriodError> {
+        setup_pwm(&timer, config, freq)?;
         // Configure PWM ports
         CCRn::<CCR1>::config_outmod(&timer, Outmod::ResetSet);
         CCRn::<CCR2>::config_outmod(&timer, Outmod::ResetSet);
         // Start the timer to run PWM
         timer.upmode();
-        Self {
+        Ok(Self {
             pwm1: PwmUninit::new(),
             pwm2: PwmUninit::new(),
-        }
+        })
     }
 }
 
 /// Collection of uninitialized PWM pins derived from timer peripheral with 7 capture-compare registers
-pub struct PwmParts7<T: CapCmpTimer7> {
+pub struct PwmParts7<'a, T: CapCmpTimer7> {
     /// PWM pin 1 (derived from capture-compare register 1)
-    pub pwm1: PwmUninit<T, CCR1>,
+    pub pwm1: PwmUninit<'a, T, CCR1>,
     /// PWM pin 2 (derived from capture-compare register 2)
-    pub pwm2: PwmUninit<T, CCR2>,
+    pub pwm2: PwmUninit<'a, T, CCR2>,
     /// PWM pin 3 (derived from capture-compare register 3)
-    pub pwm3: PwmUninit<T, CCR3>,
+    pub pwm3: PwmUninit<'a, T, CCR3>,
     /// PWM pin 4 (derived from capture-compare register 4)
-    pub pwm4: PwmUninit<T, CCR4>,
+    pub pwm4: PwmUninit<'a, T, CCR4>,
     /// PWM pin 5 (derived from capture-compare register 5)
-    pub pwm5: PwmUninit<T, CCR5>,
+    pub pwm5: PwmUninit<'a, T, CCR5>,
     /// PWM pin 6 (derived from capture-compare register 6)
-    pub pwm6: PwmUninit<T, CCR6>,
+    pub pwm6: PwmUninit<'a, T, CCR6>,
 }
 
-impl<T: CapCmpTimer7> PwmParts7<T> {
-    /// Create uninitialized PWM pins with the same period
-    pub fn new(timer: T, config: TimerConfig<T>, period: u16) -> Self {
-        setup_pwm(&timer, config, period);
+impl<'a, T: CapCmpTimer7> PwmParts7<'a, T> {
+    /// Create uninitialized PWM pins with the same period, running at the PWM frequency `freq`.
+    /// Panics if the period is longer than 2^16 cycles of the timer clock, see `try_new` for
+    /// handling that.
+    pub fn new(timer: T, config: TimerConfig<'a, T>, freq: Hertz) -> Self {
+        Self::try_new(timer, config, freq).unwrap()
+    }
+
+    /// Create uninitialized PWM pins with the same period, running at the PWM frequency `freq`, or
+    /// return an error without touching the timer if the period is longer than 2^16 cycles of the
+    /// timer clock
+    pub fn try_new(timer: T, config: TimerConfig<'a, T>, freq: Hertz) -> Result<Self, PeriodError> {
+        setup_pwm(&timer, config, freq)?;
         // Configure PWM ports
         CCRn::<CCR1>::config_outmod(&timer, Outmod::ResetSet);
         CCRn::<CCR2>::config_outmod(&timer, Outmod::ResetSet);
@@ -163,45 +195,47 @@ impl<T: CapCmpTimer7> PwmParts7<T> {
         CCRn::<CCR6>::config_outmod(&timer, Outmod::ResetSet);
         // Start the timer to run PWM
         timer.upmode();
-        Self {
+        Ok(Self {
             pwm1: PwmUninit::new(),
             pwm2: PwmUninit::new(),
             pwm3: PwmUninit::new(),
             pwm4: PwmUninit::new(),
             pwm5: PwmUninit::new(),
             pwm6: PwmUninit::new(),
-        }
+        })
     }
 }
 
 /// Uninitialized PWM pin
-pub struct PwmUninit<T, C>(PhantomData<T>, PhantomData<C>);
+pub struct PwmUninit<'a, T, C>(PhantomData<T>, PhantomData<C>, PhantomData<&'a ()>);
 
-impl<T: PwmPeriph<C>, C> PwmUninit<T, C> {
+impl<'a, T: PwmPeriph<C>, C> PwmUninit<'a, T, C> {
     /// Initialized the PWM pin by passing in the appropriately configured GPIO pin
-    pub fn init(self, pin: T::Gpio) -> Pwm<T, C> {
+    pub fn init(self, pin: T::Gpio) -> Pwm<'a, T, C> {
         Pwm {
             _timer: PhantomData,
             _ccrn: PhantomData,
+            _clk: PhantomData,
             pin,
         }
     }
 }
 
-impl<T, C> PwmUninit<T, C> {
+impl<T, C> PwmUninit<'_, T, C> {
     fn new() -> Self {
-        Self(PhantomData, PhantomData)
+        Self(PhantomData, PhantomData, PhantomData)
     }
 }
 
 /// An initialized Pwm pin
-pub struct Pwm<T: PwmPeriph<C>, C> {
+pub struct Pwm<'a, T: PwmPeriph<C>, C> {
     _timer: PhantomData<T>,
     _ccrn: PhantomData<C>,
+    _clk: PhantomData<&'a ()>,
     pin: T::Gpio,
 }
 
-impl<T: PwmPeriph<C>, C> PwmPin for Pwm<T, C> {
+impl<T: PwmPeriph<C>, C> PwmPin for Pwm<'_, T, C> {
     /// Number of cycles
     type Duty = u16;
 
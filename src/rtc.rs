@@ -1,9 +1,16 @@
 //! Real time counter
 //!
-//! Can be used as a periodic 16-bit timer
+//! Can be used as a periodic 16-bit timer. Periods are given as durations and converted into
+//! counts using the frequency of the clock source and the clock divider.
+//!
+//! The RTC can also be turned into `RtcDelay`, a delay provider that sleeps in LPM3 instead of
+//! spinning the CPU.
 
-use crate::clock::Smclk;
+use crate::clock::{Clock, Smclk, VLOCLK};
+use crate::pmm::{self, Lpm3};
+use crate::time::{self, Hertz, Microseconds, PeriodError};
 use core::marker::PhantomData;
+use embedded_hal::blocking::delay::DelayMs;
 use embedded_hal::timer::{Cancel, CountDown, Periodic};
 use msp430fr2355 as pac;
 use pac::{rtc::rtcctl::RTCSS_A, RTC};
@@ -39,47 +46,60 @@ impl RtcClockSrc for RtcVloclk {
 }
 
 /// 16-bit real-time counter
-pub struct Rtc<SRC: RtcClockSrc> {
+pub struct Rtc<'a, SRC: RtcClockSrc> {
     periph: RTC,
     _src: PhantomData<SRC>,
+    _clk: PhantomData<&'a ()>,
+    src_freq: Hertz,
+    div: RtcDiv,
 }
 
-impl Rtc<RtcVloclk> {
+impl Rtc<'_, RtcVloclk> {
     /// Convert into RTC object with VLOCLK as clock source
     pub fn new(rtc: RTC) -> Self {
         Rtc {
             periph: rtc,
             _src: PhantomData,
+            _clk: PhantomData,
+            src_freq: Hertz::from_raw(VLOCLK as u32),
+            div: RtcDiv::_1,
         }
     }
 }
 
 pub use pac::rtc::rtcctl::RTCPS_A as RtcDiv;
 
-impl<SRC: RtcClockSrc> Rtc<SRC> {
+impl<'a, SRC: RtcClockSrc> Rtc<'a, SRC> {
     /// Configure the RTC to use SMCLK as clock source. Setting comes in effect the next time RTC
     /// is started.
     #[inline]
-    pub fn use_smclk(self, _smclk: &Smclk) -> Rtc<RtcSmclk> {
+    pub fn use_smclk<'b>(self, smclk: &'b Smclk) -> Rtc<'b, RtcSmclk> {
         Rtc {
             periph: self.periph,
             _src: PhantomData,
+            _clk: PhantomData,
+            src_freq: smclk.freq(),
+            div: self.div,
         }
     }
 
     /// Configure the RTC to use VLOCLK as clock source. Setting comes in effect the next time RTC
     /// is started.
     #[inline]
-    pub fn use_vloclk(self) -> Rtc<RtcVloclk> {
+    pub fn use_vloclk(self) -> Rtc<'a, RtcVloclk> {
         Rtc {
             periph: self.periph,
             _src: PhantomData,
+            _clk: PhantomData,
+            src_freq: Hertz::from_raw(VLOCLK as u32),
+            div: self.div,
         }
     }
 
     /// Set RTC clock frequency divider
     #[inline]
     pub fn set_clk_div(&mut self, div: RtcDiv) {
+        self.div = div;
         self.periph
             .rtcctl
             .modify(|r, w| unsafe { w.bits(r.bits()) }.rtcps().variant(div));
@@ -103,6 +123,22 @@ impl<SRC: RtcClockSrc> Rtc<SRC> {
         self.periph.rtciv.read();
     }
 
+    /// Frequency at which the RTC counts, after the clock divider is applied
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        let div: u32 = match self.div {
+            RtcDiv::_1 => 1,
+            RtcDiv::_10 => 10,
+            RtcDiv::_100 => 100,
+            RtcDiv::_1000 => 1000,
+            RtcDiv::_16 => 16,
+            RtcDiv::_64 => 64,
+            RtcDiv::_256 => 256,
+            RtcDiv::_1024 => 1024,
+        };
+        self.src_freq / div
+    }
+
     /// Read current timer count, which goes up from 0 to 2^16-1
     #[inline]
     pub fn get_count(&self) -> u16 {
@@ -110,23 +146,14 @@ impl<SRC: RtcClockSrc> Rtc<SRC> {
     }
 }
 
-impl<SRC: RtcClockSrc> CountDown for Rtc<SRC> {
-    type Time = u16;
+impl<SRC: RtcClockSrc> CountDown for Rtc<'_, SRC> {
+    type Time = Microseconds;
 
+    /// Start the countdown. Panics if the period doesn't fit in 2^16 cycles of the RTC clock, see
+    /// `try_start` for handling that.
     #[inline]
-    fn start<T: Into<Self::Time>>(&mut self, count: T) {
-        self.periph
-            .rtcmod
-            .write(|w| unsafe { w.bits(count.into()) });
-        // Need to clear interrupt flag from last timer run
-        self.periph.rtciv.read();
-        self.periph.rtcctl.modify(|r, w| {
-            unsafe { w.bits(r.bits()) }
-                .rtcss()
-                .variant(SRC::CLK_SRC)
-                .rtcsr()
-                .set_bit()
-        });
+    fn start<T: Into<Self::Time>>(&mut self, period: T) {
+        self.try_start(period).unwrap();
     }
 
     #[inline]
@@ -140,7 +167,33 @@ impl<SRC: RtcClockSrc> CountDown for Rtc<SRC> {
     }
 }
 
-impl<SRC: RtcClockSrc> Cancel for Rtc<SRC> {
+impl<SRC: RtcClockSrc> Rtc<'_, SRC> {
+    /// Start the countdown, or return an error without touching the RTC if the period doesn't fit
+    /// in 2^16 cycles of the RTC clock
+    #[inline]
+    pub fn try_start<T: Into<Microseconds>>(&mut self, period: T) -> Result<(), PeriodError> {
+        let count = time::period_count(self.freq(), period.into())?;
+        self.start_count(count);
+        Ok(())
+    }
+
+    // Start counting from 0 up to and including `count`
+    #[inline]
+    fn start_count(&mut self, count: u16) {
+        self.periph.rtcmod.write(|w| unsafe { w.bits(count) });
+        // Need to clear interrupt flag from last timer run
+        self.periph.rtciv.read();
+        self.periph.rtcctl.modify(|r, w| {
+            unsafe { w.bits(r.bits()) }
+                .rtcss()
+                .variant(SRC::CLK_SRC)
+                .rtcsr()
+                .set_bit()
+        });
+    }
+}
+
+impl<SRC: RtcClockSrc> Cancel for Rtc<'_, SRC> {
     type Error = Void;
 
     #[inline]
@@ -155,4 +208,94 @@ impl<SRC: RtcClockSrc> Cancel for Rtc<SRC> {
     }
 }
 
-impl<SRC: RtcClockSrc> Periodic for Rtc<SRC> {}
+impl<SRC: RtcClockSrc> Periodic for Rtc<'_, SRC> {}
+
+/// Acknowledges an `RtcDelay` wakeup. Must be called from the RTC interrupt handler, which must
+/// be declared with `#[interrupt(wake_cpu)]` so that the CPU stays awake after the handler returns.
+#[inline]
+pub fn on_delay_interrupt() {
+    // Disable the interrupt instead of clearing the flag, so RtcDelay can tell that the RTC was
+    // the source of the wakeup
+    let rtc = unsafe { &*RTC::ptr() };
+    unsafe { rtc.rtcctl.clear_bits(|w| w.rtcie().clear_bit()) };
+}
+
+/// Delay provider that sleeps in LPM3 while the RTC counts, so long delays cost microamps instead
+/// of milliamps
+///
+/// The CPU is woken by the RTC interrupt, whose handler must call `on_delay_interrupt`. Other
+/// interrupts can still be serviced during a delay, after which the CPU goes back to sleep until
+/// the delay has elapsed. Interrupts are enabled while sleeping and restored to their previous
+/// state afterwards.
+///
+/// Delays are rounded up to the resolution of the RTC clock. With SMCLK as the clock source, SMCLK
+/// is kept running in LPM3 through its conditional request.
+pub struct RtcDelay<'a, SRC: RtcClockSrc> {
+    rtc: Rtc<'a, SRC>,
+}
+
+impl<'a, SRC: RtcClockSrc> RtcDelay<'a, SRC> {
+    /// Create a sleeping delay provider out of the RTC
+    #[inline]
+    pub fn new(rtc: Rtc<'a, SRC>) -> Self {
+        RtcDelay { rtc }
+    }
+
+    /// Release the RTC
+    #[inline]
+    pub fn free(self) -> Rtc<'a, SRC> {
+        self.rtc
+    }
+
+    // Sleep until the RTC has counted `count + 1` ticks
+    #[inline]
+    fn sleep_count(&mut self, count: u16) {
+        let gie = msp430::register::sr::read().gie();
+        // The interrupt must not fire between enabling it and going to sleep, otherwise the
+        // wakeup is lost
+        msp430::interrupt::disable();
+        self.rtc.start_count(count);
+        self.rtc.enable_interrupts();
+        while !self.rtc.periph.rtcctl.read().rtcifg().bit() {
+            pmm::enter_lpm_bits::<Lpm3>();
+            msp430::interrupt::disable();
+        }
+        self.rtc.disable_interrupts();
+        self.rtc.clear_interrupt();
+        self.rtc.cancel().ok();
+        if gie {
+            unsafe { msp430::interrupt::enable() };
+        }
+    }
+}
+
+impl<SRC: RtcClockSrc> DelayMs<u32> for RtcDelay<'_, SRC> {
+    fn delay_ms(&mut self, ms: u32) {
+        let ticks = (self.rtc.freq().raw() as u64 * ms as u64).div_ceil(1000);
+        // As with `TimerDelay`, a chunk is never shorter than 2 ticks, since the counter can't
+        // count a period of 1 tick
+        let mut ticks = if ticks == 0 { 0 } else { ticks.max(2) };
+        while ticks > 0 {
+            let mut chunk = ticks.min(0x1_0000);
+            if ticks - chunk == 1 {
+                chunk -= 1;
+            }
+            self.sleep_count((chunk - 1) as u16);
+            ticks -= chunk;
+        }
+    }
+}
+
+impl<SRC: RtcClockSrc> DelayMs<u16> for RtcDelay<'_, SRC> {
+    #[inline]
+    fn delay_ms(&mut self, ms: u16) {
+        self.delay_ms(ms as u32);
+    }
+}
+
+impl<SRC: RtcClockSrc> DelayMs<u8> for RtcDelay<'_, SRC> {
+    #[inline]
+    fn delay_ms(&mut self, ms: u8) {
+        self.delay_ms(ms as u32);
+    }
+}
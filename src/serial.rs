@@ -9,6 +9,7 @@
 use crate::clock::{Aclk, Clock, Smclk};
 use crate::gpio::{Alternate1, Pin, Pin1, Pin2, Pin3, Pin5, Pin6, Pin7, P1, P4};
 use crate::hw_traits::eusci::{EUsciUart, UartUcxStatw, UcaCtlw0, Ucssel};
+use crate::time::Hertz;
 use core::marker::PhantomData;
 use embedded_hal::serial::{Read, Write};
 use msp430fr2355 as pac;
@@ -197,13 +198,14 @@ impl<DIR> From<Pin<P4, Pin2, Alternate1<DIR>>> for UsciA1RxPin {
 
 /// Typestate for a serial interface with an unspecified clock source
 pub struct NoClockSet {
-    baudrate: u32,
+    baudrate: Hertz,
 }
 
 /// Typestate for a serial interface with a specified clock source
-pub struct ClockSet {
+pub struct ClockSet<'a> {
     baud_config: BaudConfig,
     clksel: Ucssel,
+    _clk: PhantomData<&'a ()>,
 }
 
 /// Builder object for configuring a serial UART
@@ -244,7 +246,7 @@ impl<USCI: SerialUsci> SerialConfig<USCI, NoClockSet> {
         stopbits: StopBits,
         parity: Parity,
         loopback: Loopback,
-        baudrate: u32,
+        baudrate: Hertz,
     ) -> Self {
         SerialConfig {
             order,
@@ -260,40 +262,43 @@ impl<USCI: SerialUsci> SerialConfig<USCI, NoClockSet> {
     /// Configure serial UART to use external UCLK, passing in the appropriately configured pin
     /// used as the clock signal as well as the frequency of the clock.
     #[inline(always)]
-    pub fn use_uclk<P: Into<USCI::ClockPin>>(
+    pub fn use_uclk<'a, P: Into<USCI::ClockPin>>(
         self,
         _clk_pin: P,
-        freq: u32,
-    ) -> SerialConfig<USCI, ClockSet> {
+        freq: Hertz,
+    ) -> SerialConfig<USCI, ClockSet<'a>> {
         serial_config!(
             self,
             ClockSet {
-                baud_config: calculate_baud_config(freq, self.state.baudrate),
+                baud_config: calculate_baud_config(freq.raw(), self.state.baudrate.raw()),
                 clksel: Ucssel::Uclk,
+                _clk: PhantomData,
             }
         )
     }
 
     /// Configure serial UART to use ACLK.
     #[inline(always)]
-    pub fn use_aclk(self, aclk: &Aclk) -> SerialConfig<USCI, ClockSet> {
+    pub fn use_aclk<'a>(self, aclk: &'a Aclk) -> SerialConfig<USCI, ClockSet<'a>> {
         serial_config!(
             self,
             ClockSet {
-                baud_config: calculate_baud_config(aclk.freq() as u32, self.state.baudrate),
+                baud_config: calculate_baud_config(aclk.freq().raw(), self.state.baudrate.raw()),
                 clksel: Ucssel::Aclk,
+                _clk: PhantomData,
             }
         )
     }
 
     /// Configure serial UART to use SMCLK.
     #[inline(always)]
-    pub fn use_smclk(self, smclk: &Smclk) -> SerialConfig<USCI, ClockSet> {
+    pub fn use_smclk<'a>(self, smclk: &'a Smclk) -> SerialConfig<USCI, ClockSet<'a>> {
         serial_config!(
             self,
             ClockSet {
-                baud_config: calculate_baud_config(smclk.freq(), self.state.baudrate),
+                baud_config: calculate_baud_config(smclk.freq().raw(), self.state.baudrate.raw()),
                 clksel: Ucssel::Smclk,
+                _clk: PhantomData,
             }
         )
     }
@@ -307,11 +312,18 @@ struct BaudConfig {
 }
 
 #[inline]
-fn calculate_baud_config(clk_freq: u32, bps: u32) -> BaudConfig {
+const fn calculate_baud_config(clk_freq: u32, bps: u32) -> BaudConfig {
     // Prevent division by 0
-    let bps = bps.max(1);
+    let bps = if bps == 0 { 1 } else { bps };
     // Ensure n stays within the 16 bit boundary
-    let n = (clk_freq / bps).max(1).min(0xFFFF);
+    let n = clk_freq / bps;
+    let n = if n < 1 {
+        1
+    } else if n > 0xFFFF {
+        0xFFFF
+    } else {
+        n
+    };
 
     let brs = lookup_brs(clk_freq, bps);
 
@@ -337,6 +349,39 @@ fn calculate_baud_config(clk_freq: u32, bps: u32) -> BaudConfig {
     }
 }
 
+impl BaudConfig {
+    // Average length of a bit in eighths of a clock cycle, including the modulation bits
+    #[inline]
+    const fn bit_len_eighths(&self) -> u32 {
+        let modulation = self.brs.count_ones();
+        if self.ucos16 {
+            (self.br as u32 * 16 + self.brf as u32) * 8 + modulation
+        } else {
+            self.br as u32 * 8 + modulation
+        }
+    }
+}
+
+/// Largest baud rate error tolerated by `validate_baudrate`, in percent
+pub const MAX_BAUD_ERROR_PERCENT: u32 = 2;
+
+/// Checks that `baudrate` can be generated from a UART clock running at `clk_freq` with an error
+/// no larger than `MAX_BAUD_ERROR_PERCENT`, and returns `baudrate` unchanged. Panics otherwise, so
+/// using this in a `const` item turns an unreachable baud rate into a build failure.
+#[inline]
+pub const fn validate_baudrate(clk_freq: Hertz, baudrate: Hertz) -> Hertz {
+    let (clk, bps) = (clk_freq.raw(), baudrate.raw());
+    assert!(bps != 0, "baud rate is zero");
+    let actual = calculate_baud_config(clk, bps).bit_len_eighths() as u64;
+    let ideal = clk as u64 * 8 / bps as u64;
+    let error = actual.abs_diff(ideal);
+    assert!(
+        ideal != 0 && error * 100 <= ideal * MAX_BAUD_ERROR_PERCENT as u64,
+        "baud rate error above tolerance"
+    );
+    baudrate
+}
+
 // Data from table 22-4 of MSP430FR4xx and MSP430FR2xx family user's guide (Rev. I)
 const BRS_LOOKUP_KEYS: [u16; 36] = [
     0x0000, 0x00d9, 0x0125, 0x0156, 0x019a, 0x0201, 0x024a, 0x02ac, 0x036f, 0x038f, 0x0401, 0x04cd,
@@ -351,7 +396,7 @@ const BRS_LOOKUP_VALS: [u8; 36] = [
 ];
 
 #[inline(always)]
-fn binary_search_brs_table(res: u16) -> u8 {
+const fn binary_search_brs_table(res: u16) -> u8 {
     let mut low: usize = 0;
     let mut high: usize = BRS_LOOKUP_KEYS.len() - 1;
     while low != high {
@@ -375,7 +420,7 @@ fn binary_search_brs_table(res: u16) -> u8 {
 }
 
 #[inline(always)]
-fn lookup_brs(clk_freq: u32, bps: u32) -> u8 {
+const fn lookup_brs(clk_freq: u32, bps: u32) -> u8 {
     let modulo = clk_freq % bps;
     // 12 fractional bit fixed point result
     let fixed_point_result: u32 = (modulo << 12) / bps;
@@ -384,12 +429,13 @@ fn lookup_brs(clk_freq: u32, bps: u32) -> u8 {
     binary_search_brs_table(fixed_point_result as u16)
 }
 
-impl<USCI: SerialUsci> SerialConfig<USCI, ClockSet> {
+impl<'a, USCI: SerialUsci> SerialConfig<USCI, ClockSet<'a>> {
     #[inline]
     fn config_hw(self) {
         let ClockSet {
             baud_config,
             clksel,
+            ..
         } = self.state;
         let usci = self.usci;
 
@@ -415,30 +461,30 @@ impl<USCI: SerialUsci> SerialConfig<USCI, ClockSet> {
         self,
         _tx: T,
         _rx: R,
-    ) -> (Tx<USCI>, Rx<USCI>) {
+    ) -> (Tx<'a, USCI>, Rx<'a, USCI>) {
         self.config_hw();
-        (Tx(PhantomData), Rx(PhantomData))
+        (Tx(PhantomData, PhantomData), Rx(PhantomData, PhantomData))
     }
 
     /// Perform hardware configuration and create Tx pin from appropriate GPIO
     #[inline]
-    pub fn tx_only<T: Into<USCI::TxPin>>(self, _tx: T) -> Tx<USCI> {
+    pub fn tx_only<T: Into<USCI::TxPin>>(self, _tx: T) -> Tx<'a, USCI> {
         self.config_hw();
-        Tx(PhantomData)
+        Tx(PhantomData, PhantomData)
     }
 
     /// Perform hardware configuration and create Rx pin from appropriate GPIO
     #[inline]
-    pub fn rx_only<R: Into<USCI::RxPin>>(self, _rx: R) -> Rx<USCI> {
+    pub fn rx_only<R: Into<USCI::RxPin>>(self, _rx: R) -> Rx<'a, USCI> {
         self.config_hw();
-        Rx(PhantomData)
+        Rx(PhantomData, PhantomData)
     }
 }
 
 /// Serial transmitter pin
-pub struct Tx<USCI: SerialUsci>(PhantomData<USCI>);
+pub struct Tx<'a, USCI: SerialUsci>(PhantomData<USCI>, PhantomData<&'a ()>);
 
-impl<USCI: SerialUsci> Tx<USCI> {
+impl<USCI: SerialUsci> Tx<'_, USCI> {
     /// Enable Tx interrupts, which fire when ready to send.
     #[inline(always)]
     pub fn enable_tx_interrupts(&mut self) {
@@ -454,7 +500,7 @@ impl<USCI: SerialUsci> Tx<USCI> {
     }
 }
 
-impl<USCI: SerialUsci> Write<u8> for Tx<USCI> {
+impl<USCI: SerialUsci> Write<u8> for Tx<'_, USCI> {
     type Error = void::Void;
 
     /// Due to errata USCI42, UCTXCPTIFG will fire every time a byte is done transmitting,
@@ -484,12 +530,12 @@ impl<USCI: SerialUsci> Write<u8> for Tx<USCI> {
     }
 }
 
-impl<USCI: SerialUsci> embedded_hal::blocking::serial::write::Default<u8> for Tx<USCI> {}
+impl<USCI: SerialUsci> embedded_hal::blocking::serial::write::Default<u8> for Tx<'_, USCI> {}
 
 /// Serial receiver pin
-pub struct Rx<USCI: SerialUsci>(PhantomData<USCI>);
+pub struct Rx<'a, USCI: SerialUsci>(PhantomData<USCI>, PhantomData<&'a ()>);
 
-impl<USCI: SerialUsci> Rx<USCI> {
+impl<USCI: SerialUsci> Rx<'_, USCI> {
     /// Enable Rx interrupts, which fire when ready to read
     #[inline(always)]
     pub fn enable_rx_interrupts(&mut self) {
@@ -522,7 +568,7 @@ pub enum RecvError {
     Overrun(u8),
 }
 
-impl<USCI: SerialUsci> Read<u8> for Rx<USCI> {
+impl<USCI: SerialUsci> Read<u8> for Rx<'_, USCI> {
     type Error = RecvError;
 
     #[inline]
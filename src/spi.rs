@@ -1,10 +1,11 @@
 //! embedded_hal SPI implmentation
 use crate::hal::spi::{Mode, Phase, Polarity};
 use crate::{
-    clock::{Aclk, Smclk},
+    clock::{Aclk, Clock, Smclk},
     gpio::{Alternate1, Pin, Pin0, Pin1, Pin2, Pin3, Pin4, Pin5, Pin6, Pin7, P1, P4},
     hal,
     hw_traits::eusci::{EusciSPI, Ucmode, Ucssel, UcxSpiCtw0},
+    time::{self, Hertz},
 };
 use core::marker::PhantomData;
 use embedded_hal::spi::FullDuplex;
@@ -195,16 +196,35 @@ impl<DIR> From<Pin<P4, Pin4, Alternate1<DIR>>> for UsciB1STEPin {
     }
 }
 
+/// Checks that an SPI clock of `freq` can be derived from a source clock running at `clk_freq`,
+/// and returns the bit rate actually achieved after division. Panics if the source clock is slower
+/// than `freq` or too fast to divide down to it, so using this in a `const` item turns an
+/// unreachable bit rate into a build failure.
+#[inline]
+pub const fn validate_bit_rate(clk_freq: Hertz, freq: Hertz) -> Hertz {
+    assert!(freq.raw() != 0, "SPI bit rate is zero");
+    assert!(
+        clk_freq.raw() >= freq.raw(),
+        "clock too slow for SPI bit rate"
+    );
+    assert!(
+        clk_freq.raw().div_ceil(freq.raw()) <= u16::MAX as u32,
+        "clock too fast for SPI bit rate"
+    );
+    Hertz::from_raw(clk_freq.raw() / time::divisor(clk_freq, freq) as u32)
+}
+
 /// Struct used to configure a SPI bus
-pub struct SPIBusConfig<USCI: EUsciSPIBus> {
+pub struct SPIBusConfig<'a, USCI: EUsciSPIBus> {
     usci: USCI,
     prescaler: u16,
+    _clk: PhantomData<&'a ()>,
 
     // Register configs
     ctlw0: UcxSpiCtw0,
 }
 
-impl<USCI: EUsciSPIBus> SPIBusConfig<USCI> {
+impl<'a, USCI: EUsciSPIBus> SPIBusConfig<'a, USCI> {
     /// Create a new configuration for setting up a EUSCI peripheral in SPI mode
     pub fn new(usci: USCI, mode: Mode, msb_first: bool) -> Self {
         let ctlw0 = UcxSpiCtw0 {
@@ -229,22 +249,25 @@ impl<USCI: EUsciSPIBus> SPIBusConfig<USCI> {
         SPIBusConfig {
             usci,
             prescaler: 0,
+            _clk: PhantomData,
             ctlw0,
         }
     }
 
-    /// Configures this peripheral to use smclk
+    /// Configures this peripheral to use smclk, dividing it down so the SPI bit clock runs at no
+    /// more than `freq`
     #[inline]
-    pub fn use_smclk(&mut self, _smclk: &Smclk, clk_divisor: u16) {
+    pub fn use_smclk(&mut self, smclk: &'a Smclk, freq: Hertz) {
         self.ctlw0.ucssel = Ucssel::Smclk;
-        self.prescaler = clk_divisor;
+        self.prescaler = time::divisor(smclk.freq(), freq);
     }
 
-    /// Configures this peripheral to use aclk
+    /// Configures this peripheral to use aclk, dividing it down so the SPI bit clock runs at no
+    /// more than `freq`
     #[inline]
-    pub fn use_aclk(&mut self, _aclk: &Aclk, clk_divisor: u16) {
+    pub fn use_aclk(&mut self, aclk: &'a Aclk, freq: Hertz) {
         self.ctlw0.ucssel = Ucssel::Aclk;
-        self.prescaler = clk_divisor;
+        self.prescaler = time::divisor(aclk.freq(), freq);
     }
 
     /// Performs hardware configuration and creates an SPI bus
@@ -259,9 +282,9 @@ impl<USCI: EUsciSPIBus> SPIBusConfig<USCI> {
         _mosi: SI,
         _sclk: CLK,
         _cs: STE,
-    ) -> SPIPins<USCI> {
+    ) -> SPIPins<'a, USCI> {
         self.configure_hw();
-        SPIPins(PhantomData)
+        SPIPins(PhantomData, PhantomData)
     }
 
     #[inline]
@@ -280,9 +303,9 @@ impl<USCI: EUsciSPIBus> SPIBusConfig<USCI> {
 }
 
 /// Represents a group of pins configured for SPI communication
-pub struct SPIPins<USCI: EUsciSPIBus>(PhantomData<USCI>);
+pub struct SPIPins<'a, USCI: EUsciSPIBus>(PhantomData<USCI>, PhantomData<&'a ()>);
 
-impl<USCI: EUsciSPIBus> SPIPins<USCI> {
+impl<USCI: EUsciSPIBus> SPIPins<'_, USCI> {
     /// Enable or disable Rx interrupts, which fire when a byte is ready to be read
     #[inline(always)]
     pub fn rx_interrupt_set(&mut self, flag: bool) {
@@ -312,7 +335,7 @@ pub enum SPIErr {
     Unimplemented = 0,
 }
 
-impl<USCI: EUsciSPIBus> hal::blocking::spi::Transfer<u8> for SPIPins<USCI> {
+impl<USCI: EUsciSPIBus> hal::blocking::spi::Transfer<u8> for SPIPins<'_, USCI> {
     type Error = SPIErr;
 
     fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
@@ -324,7 +347,7 @@ impl<USCI: EUsciSPIBus> hal::blocking::spi::Transfer<u8> for SPIPins<USCI> {
     }
 }
 
-impl<USCI: EUsciSPIBus> hal::blocking::spi::Write<u8> for SPIPins<USCI> {
+impl<USCI: EUsciSPIBus> hal::blocking::spi::Write<u8> for SPIPins<'_, USCI> {
     type Error = SPIErr;
 
     fn write(&mut self, words: &[u8]) -> Result<(), SPIErr> {
@@ -336,7 +359,7 @@ impl<USCI: EUsciSPIBus> hal::blocking::spi::Write<u8> for SPIPins<USCI> {
     }
 }
 
-impl<USCI: EUsciSPIBus> FullDuplex<u8> for SPIPins<USCI> {
+impl<USCI: EUsciSPIBus> FullDuplex<u8> for SPIPins<'_, USCI> {
     type Error = SPIErr;
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
         let usci = unsafe { USCI::steal() };
@@ -0,0 +1,192 @@
+//! System configuration and non-maskable interrupts
+//!
+//! `SysCfg` selects whether the RST/NMI pin resets the device or raises an NMI, and enables the
+//! NMI sources that live in the SFR registers. The oscillator fault NMI is controlled through
+//! `clock::OscFault` instead, since it's tied to the XT1 state.
+//!
+//! NMIs come in two vectors: system NMIs (`SYSNMI`), such as vacant memory accesses and FRAM bit
+//! errors, and user NMIs (`UNMI`), from the NMI pin and oscillator faults. `NmiVectors` decodes
+//! the source of each, clearing the corresponding flag, and is meant to be used from the NMI
+//! handlers.
+
+use crate::jtag::JtagMailbox;
+use core::marker::PhantomData;
+use msp430fr2355 as pac;
+use pac::{SFR, SYS};
+
+/// Pull resistor on the RST/NMI pin
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RstPull {
+    /// No pull resistor
+    None,
+    /// Pull-up resistor, which is the reset default
+    Up,
+    /// Pull-down resistor
+    Down,
+}
+
+/// Edge of the RST/NMI pin that triggers an NMI
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NmiEdge {
+    /// Rising edge
+    Rising,
+    /// Falling edge
+    Falling,
+}
+
+/// System configuration
+pub struct SysCfg {
+    _sys: SYS,
+    jtag_mailbox: Option<JtagMailbox>,
+}
+
+impl SysCfg {
+    /// Take the SYS peripheral, returning the configuration and the NMI vector decoders
+    #[inline]
+    pub fn new(sys: SYS) -> (Self, NmiVectors) {
+        let cfg = SysCfg {
+            _sys: sys,
+            jtag_mailbox: Some(JtagMailbox::new()),
+        };
+        (cfg, NmiVectors(PhantomData))
+    }
+
+    /// Takes the JTAG mailbox, which can only be done once
+    #[inline]
+    pub fn take_jtag_mailbox(&mut self) -> Option<JtagMailbox> {
+        self.jtag_mailbox.take()
+    }
+
+    #[inline(always)]
+    fn sfr() -> &'static pac::sfr::RegisterBlock {
+        unsafe { &*SFR::ptr() }
+    }
+
+    /// Use the RST/NMI pin as a reset input
+    #[inline]
+    pub fn rst_pin_reset(&mut self, pull: RstPull) {
+        Self::sfr()
+            .sfrrpcr
+            .modify(|_, w| pull_bits(w, pull).sysnmi().clear_bit());
+    }
+
+    /// Use the RST/NMI pin as an NMI input. The NMI itself is enabled with `nmi_pin_interrupt`.
+    #[inline]
+    pub fn rst_pin_nmi(&mut self, edge: NmiEdge, pull: RstPull) {
+        Self::sfr().sfrrpcr.modify(|_, w| {
+            pull_bits(w, pull)
+                .sysnmiies()
+                .bit(edge == NmiEdge::Falling)
+                .sysnmi()
+                .set_bit()
+        });
+    }
+
+    /// Enable or disable the NMI from the RST/NMI pin
+    #[inline]
+    pub fn nmi_pin_interrupt(&mut self, enable: bool) {
+        let sfr = Self::sfr();
+        if enable {
+            // Changing the pin function can latch a spurious edge
+            unsafe { sfr.sfrifg1.clear_bits(|w| w.nmiifg().clear_bit()) };
+            unsafe { sfr.sfrie1.set_bits(|w| w.nmiie().set_bit()) };
+        } else {
+            unsafe { sfr.sfrie1.clear_bits(|w| w.nmiie().clear_bit()) };
+        }
+    }
+
+    /// Enable or disable the NMI raised by accesses to vacant memory
+    #[inline]
+    pub fn vacant_memory_interrupt(&mut self, enable: bool) {
+        let sfr = Self::sfr();
+        if enable {
+            unsafe { sfr.sfrie1.set_bits(|w| w.vmaie().set_bit()) };
+        } else {
+            unsafe { sfr.sfrie1.clear_bits(|w| w.vmaie().clear_bit()) };
+        }
+    }
+
+    /// Enable or disable the NMIs raised when the JTAG mailbox input is filled or the output is
+    /// emptied
+    #[inline]
+    pub fn jtag_mailbox_interrupts(&mut self, input: bool, output: bool) {
+        Self::sfr()
+            .sfrie1
+            .modify(|_, w| w.jmbinie().bit(input).jmboutie().bit(output));
+    }
+}
+
+#[inline(always)]
+fn pull_bits(w: &mut pac::sfr::sfrrpcr::W, pull: RstPull) -> &mut pac::sfr::sfrrpcr::W {
+    match pull {
+        RstPull::None => w.sysrstre().clear_bit(),
+        RstPull::Up => w.sysrstre().set_bit().sysrstup().set_bit(),
+        RstPull::Down => w.sysrstre().set_bit().sysrstup().clear_bit(),
+    }
+}
+
+/// Source of a system NMI
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemNmi {
+    /// No pending interrupt
+    NoInterrupt,
+    /// SVS low-power reset entry
+    SvsLowPowerReset,
+    /// Uncorrectable FRAM bit error
+    FramUncorrectable,
+    /// FRAM access time error
+    FramAccessTime,
+    /// Vacant memory access
+    VacantMemoryAccess,
+    /// JTAG mailbox input filled
+    JtagMailboxIn,
+    /// JTAG mailbox output emptied
+    JtagMailboxOut,
+    /// Correctable FRAM bit error
+    FramCorrectable,
+}
+
+/// Source of a user NMI
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserNmi {
+    /// No pending interrupt
+    NoInterrupt,
+    /// Edge on the RST/NMI pin
+    NmiPin,
+    /// Oscillator fault
+    OscFault,
+}
+
+/// NMI vector registers for determining which source caused an NMI
+pub struct NmiVectors(PhantomData<SYS>);
+
+impl NmiVectors {
+    /// Read the system NMI vector. Automatically resets the corresponding interrupt flag.
+    #[inline]
+    pub fn system_vector(&mut self) -> SystemNmi {
+        let sys = unsafe { &*SYS::ptr() };
+        match sys.syssniv.read().bits() {
+            0x02 => SystemNmi::SvsLowPowerReset,
+            0x04 => SystemNmi::FramUncorrectable,
+            0x06 => SystemNmi::FramAccessTime,
+            0x12 => SystemNmi::VacantMemoryAccess,
+            0x14 => SystemNmi::JtagMailboxIn,
+            0x16 => SystemNmi::JtagMailboxOut,
+            0x18 => SystemNmi::FramCorrectable,
+            // Reserved values are never reported
+            _ => SystemNmi::NoInterrupt,
+        }
+    }
+
+    /// Read the user NMI vector. Automatically resets the corresponding interrupt flag.
+    #[inline]
+    pub fn user_vector(&mut self) -> UserNmi {
+        let sys = unsafe { &*SYS::ptr() };
+        match sys.sysuniv.read().bits() {
+            0x02 => UserNmi::NmiPin,
+            0x04 => UserNmi::OscFault,
+            // Reserved values are never reported
+            _ => UserNmi::NoInterrupt,
+        }
+    }
+}
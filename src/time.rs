@@ -0,0 +1,70 @@
+//! Frequency and duration units
+//!
+//! Clock frequencies, baud rates, and timer periods throughout the HAL are expressed with these
+//! types, which are aliases of the [`fugit`] types used across the embedded ecosystem. Values are
+//! created with the `RateExtU32` and `ExtU32` extension traits, such as `8.MHz()`,
+//! `115_200.Hz()`, or `500.millis()`. Conversions between units of the same kind are done at
+//! compile time.
+//!
+//! [`fugit`]: https://docs.rs/fugit
+
+pub use fugit::{ExtU32, RateExtU32};
+
+/// Frequency in hertz
+pub type Hertz = fugit::HertzU32;
+/// Frequency in kilohertz
+pub type KiloHertz = fugit::KilohertzU32;
+/// Frequency in megahertz
+pub type MegaHertz = fugit::MegahertzU32;
+
+/// Duration in microseconds
+pub type Microseconds = fugit::MicrosDurationU32;
+/// Duration in milliseconds
+pub type Milliseconds = fugit::MillisDurationU32;
+
+/// Number of cycles of a clock running at `freq` that fit into `duration`, rounded to the nearest
+/// cycle. Saturates at `u32::MAX`.
+#[inline]
+pub(crate) fn cycles(freq: Hertz, duration: Microseconds) -> u32 {
+    let cycles = (freq.raw() as u64 * duration.ticks() as u64 + 500_000) / 1_000_000;
+    cycles.min(u32::MAX as u64) as u32
+}
+
+/// Error converting a duration into the threshold of a 16-bit counter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeriodError {
+    /// The period lasts more than 2^16 cycles of the counter clock, so a slower clock or a larger
+    /// clock divider is needed
+    TooLong,
+}
+
+/// Threshold for a 16-bit counter clocked at `freq` that counts from 0 up to and including the
+/// threshold, such that one full count lasts `duration`. Periods are at least 2 cycles long, since
+/// a threshold of 0 stops the counter.
+#[inline]
+pub(crate) fn period_count(freq: Hertz, duration: Microseconds) -> Result<u16, PeriodError> {
+    counter_threshold(cycles(freq, duration))
+}
+
+/// Threshold for a 16-bit counter whose full count lasts `cycles`, which is raised to at least 2
+#[inline]
+pub(crate) fn counter_threshold(cycles: u32) -> Result<u16, PeriodError> {
+    match cycles.max(2) {
+        cycles @ 2..=0x1_0000 => Ok((cycles - 1) as u16),
+        _ => Err(PeriodError::TooLong),
+    }
+}
+
+/// Smallest clock divisor that brings `src` down to at most `target`, limited to 16 bits
+#[inline]
+pub(crate) const fn divisor(src: Hertz, target: Hertz) -> u16 {
+    let target = if target.raw() == 0 { 1 } else { target.raw() };
+    let div = src.raw().div_ceil(target);
+    if div < 1 {
+        1
+    } else if div > u16::MAX as u32 {
+        u16::MAX
+    } else {
+        div as u16
+    }
+}
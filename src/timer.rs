@@ -7,9 +7,10 @@
 //! This module also contains traits used by other HAL modules that depend on TimerB, such as
 //! `Capture` and `Pwm`.
 
-use crate::clock::{Aclk, Smclk};
+use crate::clock::{Aclk, Clock, Smclk};
 use crate::gpio::{Alternate1, Floating, Input, Pin, Pin2, Pin6, Pin7, P2, P5, P6};
 use crate::hw_traits::timerb::{CCRn, Tbssel, TimerB};
+use crate::time::{self, Hertz, Microseconds, PeriodError};
 use core::marker::PhantomData;
 use embedded_hal::timer::{Cancel, CountDown, Periodic};
 use msp430fr2355 as pac;
@@ -68,20 +69,26 @@ impl CapCmpTimer7 for pac::TB3 {}
 /// Configuration object for the TimerB peripheral
 ///
 /// Used to configure `Timer`, `Capture`, and `Pwm`, which all use the TimerB peripheral.
-pub struct TimerConfig<T: TimerPeriph> {
+/// Timers and PWMs created from it borrow the clock object it was made from, so the clocks can't
+/// be reconfigured while they still count at the old frequency.
+pub struct TimerConfig<'a, T: TimerPeriph> {
     _timer: PhantomData<T>,
+    _clk: PhantomData<&'a ()>,
     sel: Tbssel,
+    src_freq: Hertz,
     div: TimerDiv,
     ex_div: TimerExDiv,
 }
 
-impl<T: TimerPeriph> TimerConfig<T> {
+impl<'a, T: TimerPeriph> TimerConfig<'a, T> {
     /// Configure timer clock source to ACLK
     #[inline]
-    pub fn aclk(_aclk: &Aclk) -> Self {
+    pub fn aclk(aclk: &'a Aclk) -> Self {
         TimerConfig {
             _timer: PhantomData,
+            _clk: PhantomData,
             sel: Tbssel::Aclk,
+            src_freq: aclk.freq(),
             div: TimerDiv::_1,
             ex_div: TimerExDiv::_1,
         }
@@ -89,21 +96,25 @@ impl<T: TimerPeriph> TimerConfig<T> {
 
     /// Configure timer clock source to SMCLK
     #[inline]
-    pub fn smclk(_smclk: &Smclk) -> Self {
+    pub fn smclk(smclk: &'a Smclk) -> Self {
         TimerConfig {
             _timer: PhantomData,
+            _clk: PhantomData,
             sel: Tbssel::Smclk,
+            src_freq: smclk.freq(),
             div: TimerDiv::_1,
             ex_div: TimerExDiv::_1,
         }
     }
 
-    /// Configure timer clock source to TBCLK
+    /// Configure timer clock source to TBCLK, passing in the frequency of the external clock
     #[inline]
-    pub fn tbclk(_pin: T::Tbxclk) -> Self {
+    pub fn tbclk(_pin: T::Tbxclk, freq: Hertz) -> Self {
         TimerConfig {
             _timer: PhantomData,
+            _clk: PhantomData,
             sel: Tbssel::Tbxclk,
+            src_freq: freq,
             div: TimerDiv::_1,
             ex_div: TimerExDiv::_1,
         }
@@ -114,12 +125,21 @@ impl<T: TimerPeriph> TimerConfig<T> {
     pub fn clk_div(self, div: TimerDiv, ex_div: TimerExDiv) -> Self {
         TimerConfig {
             _timer: PhantomData,
+            _clk: PhantomData,
             sel: self.sel,
+            src_freq: self.src_freq,
             div,
             ex_div,
         }
     }
 
+    /// Frequency of the timer clock after both dividers are applied
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        let div = (1u32 << (self.div as u8)) * (self.ex_div as u32 + 1);
+        self.src_freq / div
+    }
+
     #[inline]
     pub(crate) fn write_regs(self, timer: &T) {
         timer.reset();
@@ -129,75 +149,85 @@ impl<T: TimerPeriph> TimerConfig<T> {
 }
 
 /// Main timer and sub-timers for timer peripherals with 3 capture-compare registers
-pub struct TimerParts3<T: CapCmpTimer3> {
+pub struct TimerParts3<'a, T: CapCmpTimer3> {
     /// Main timer
-    pub timer: Timer<T>,
+    pub timer: Timer<'a, T>,
     /// Timer interrupt vector
     pub tbxiv: TBxIV<T>,
     /// Sub-timer 1 (derived from CCR1 register)
-    pub subtimer1: SubTimer<T, CCR1>,
+    pub subtimer1: SubTimer<'a, T, CCR1>,
     /// Sub-timer 2 (derived from CCR2 register)
-    pub subtimer2: SubTimer<T, CCR2>,
+    pub subtimer2: SubTimer<'a, T, CCR2>,
 }
 
-impl<T: CapCmpTimer3> TimerParts3<T> {
+impl<'a, T: CapCmpTimer3> TimerParts3<'a, T> {
     /// Create new set of timers out of a TBx peripheral
     #[inline(always)]
-    pub fn new(_timer: T, config: TimerConfig<T>) -> Self {
+    pub fn new(_timer: T, config: TimerConfig<'a, T>) -> Self {
+        let freq = config.freq();
         config.write_regs(unsafe { &T::steal() });
         Self {
-            timer: Timer::new(),
+            timer: Timer::new(freq),
             tbxiv: TBxIV(PhantomData),
-            subtimer1: SubTimer::new(),
-            subtimer2: SubTimer::new(),
+            subtimer1: SubTimer::new(freq),
+            subtimer2: SubTimer::new(freq),
         }
     }
 }
 
 /// Main timer and sub-timers for timer peripherals with 7 capture-compare registers
-pub struct TimerParts7<T: CapCmpTimer7> {
+pub struct TimerParts7<'a, T: CapCmpTimer7> {
     /// Main timer
-    pub timer: Timer<T>,
+    pub timer: Timer<'a, T>,
     /// Timer interrupt vector
     pub tbxiv: TBxIV<T>,
     /// Sub-timer 1 (derived from CCR1 register)
-    pub subtimer1: SubTimer<T, CCR1>,
+    pub subtimer1: SubTimer<'a, T, CCR1>,
     /// Sub-timer 2 (derived from CCR2 register)
-    pub subtimer2: SubTimer<T, CCR2>,
+    pub subtimer2: SubTimer<'a, T, CCR2>,
     /// Sub-timer 3 (derived from CCR3 register)
-    pub subtimer3: SubTimer<T, CCR3>,
+    pub subtimer3: SubTimer<'a, T, CCR3>,
     /// Sub-timer 4 (derived from CCR4 register)
-    pub subtimer4: SubTimer<T, CCR4>,
+    pub subtimer4: SubTimer<'a, T, CCR4>,
     /// Sub-timer 5 (derived from CCR5 register)
-    pub subtimer5: SubTimer<T, CCR5>,
+    pub subtimer5: SubTimer<'a, T, CCR5>,
     /// Sub-timer 6 (derived from CCR6 register)
-    pub subtimer6: SubTimer<T, CCR6>,
+    pub subtimer6: SubTimer<'a, T, CCR6>,
 }
 
-impl<T: CapCmpTimer7> TimerParts7<T> {
+impl<'a, T: CapCmpTimer7> TimerParts7<'a, T> {
     /// Create new set of timers out of a TBx peripheral
     #[inline(always)]
-    pub fn new(_timer: T, config: TimerConfig<T>) -> Self {
+    pub fn new(_timer: T, config: TimerConfig<'a, T>) -> Self {
+        let freq = config.freq();
         config.write_regs(unsafe { &T::steal() });
         Self {
-            timer: Timer::new(),
+            timer: Timer::new(freq),
             tbxiv: TBxIV(PhantomData),
-            subtimer1: SubTimer::new(),
-            subtimer2: SubTimer::new(),
-            subtimer3: SubTimer::new(),
-            subtimer4: SubTimer::new(),
-            subtimer5: SubTimer::new(),
-            subtimer6: SubTimer::new(),
+            subtimer1: SubTimer::new(freq),
+            subtimer2: SubTimer::new(freq),
+            subtimer3: SubTimer::new(freq),
+            subtimer4: SubTimer::new(freq),
+            subtimer5: SubTimer::new(freq),
+            subtimer6: SubTimer::new(freq),
         }
     }
 }
 
 /// Main periodic countdown timer
-pub struct Timer<T: TimerPeriph>(PhantomData<T>);
+pub struct Timer<'a, T: TimerPeriph> {
+    _timer: PhantomData<T>,
+    _clk: PhantomData<&'a ()>,
+    freq: Hertz,
+}
 
-impl<T: TimerPeriph> Timer<T> {
-    fn new() -> Self {
-        Self(PhantomData)
+impl<T: TimerPeriph> Timer<'_, T> {
+    fn new(freq: Hertz) -> Self {
+        Self {
+            _timer: PhantomData,
+            _clk: PhantomData,
+            freq,
+        }
     }
 }
 
@@ -205,11 +235,21 @@ impl<T: TimerPeriph> Timer<T> {
 ///
 /// Each sub-timer has its own interrupt mechanism and threshold, but shares its countdown value
 /// with its main timer.
-pub struct SubTimer<T: CapCmp<C>, C>(PhantomData<T>, PhantomData<C>);
+pub struct SubTimer<'a, T: CapCmp<C>, C> {
+    _timer: PhantomData<T>,
+    _clk: PhantomData<&'a ()>,
+    _ccr: PhantomData<C>,
+    freq: Hertz,
+}
 
-impl<T: CapCmp<C>, C> SubTimer<T, C> {
-    fn new() -> Self {
-        Self(PhantomData, PhantomData)
+impl<T: CapCmp<C>, C> SubTimer<'_, T, C> {
+    fn new(freq: Hertz) -> Self {
+        Self {
+            _timer: PhantomData,
+            _clk: PhantomData,
+            _ccr: PhantomData,
+            freq,
+        }
     }
 }
 
@@ -260,15 +300,14 @@ impl<T: TimerB> TBxIV<T> {
     }
 }
 
-impl<T: TimerPeriph + CapCmp<CCR0>> CountDown for Timer<T> {
-    type Time = u16;
+impl<T: TimerPeriph + CapCmp<CCR0>> CountDown for Timer<'_, T> {
+    type Time = Microseconds;
 
+    /// Start the countdown. Panics if the period doesn't fit in 2^16 cycles of the timer clock,
+    /// see `try_start` for handling that.
     #[inline]
-    fn start<U: Into<Self::Time>>(&mut self, count: U) {
-        let timer = unsafe { T::steal() };
-        timer.stop();
-        timer.set_ccrn(count.into());
-        timer.upmode();
+    fn start<U: Into<Self::Time>>(&mut self, period: U) {
+        self.try_start(period).unwrap();
     }
 
     #[inline]
@@ -283,7 +322,7 @@ impl<T: TimerPeriph + CapCmp<CCR0>> CountDown for Timer<T> {
     }
 }
 
-impl<T: TimerPeriph + CapCmp<CCR0>> Cancel for Timer<T> {
+impl<T: TimerPeriph + CapCmp<CCR0>> Cancel for Timer<'_, T> {
     type Error = void::Void;
 
     #[inline(always)]
@@ -294,9 +333,29 @@ impl<T: TimerPeriph + CapCmp<CCR0>> Cancel for Timer<T> {
     }
 }
 
-impl<T: TimerPeriph> Periodic for Timer<T> {}
+impl<T: TimerPeriph> Periodic for Timer<'_, T> {}
+
+impl<T: TimerPeriph + CapCmp<CCR0>> Timer<'_, T> {
+    /// Start the countdown, or return an error without touching the timer if the period doesn't
+    /// fit in 2^16 cycles of the timer clock
+    #[inline]
+    pub fn try_start<U: Into<Microseconds>>(&mut self, period: U) -> Result<(), PeriodError> {
+        let count = time::period_count(self.freq, period.into())?;
+        let timer = unsafe { T::steal() };
+        timer.stop();
+        timer.set_ccrn(count);
+        timer.upmode();
+        Ok(())
+    }
+}
+
+impl<T: TimerPeriph> Timer<'_, T> {
+    /// Frequency of the timer clock
+    #[inline(always)]
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
 
-impl<T: TimerPeriph> Timer<T> {
     /// Enable timer countdown expiration interrupts
     #[inline(always)]
     pub fn enable_interrupts(&mut self) {
@@ -312,16 +371,19 @@ impl<T: TimerPeriph> Timer<T> {
     }
 }
 
-impl<T: CapCmp<C>, C> SubTimer<T, C> {
+impl<T: CapCmp<C>, C> SubTimer<'_, T, C> {
     #[inline]
-    /// Set the threshold for one of the sub-timers. Once the main timer counts to this threshold
-    /// the sub-timer will fire. Note that the main timer resets once it counts to its own
-    /// threshold, not the sub-timer thresholds. It follows that the sub-timer threshold must be
-    /// less than the main threshold for it to fire.
-    pub fn set_count(&mut self, count: u16) {
+    /// Set the threshold for one of the sub-timers as a delay from the start of the main timer
+    /// period. Once the main timer counts to this threshold the sub-timer will fire. Note that the
+    /// main timer resets once it counts to its own threshold, not the sub-timer thresholds. It
+    /// follows that the sub-timer threshold must be less than the main threshold for it to fire.
+    /// Returns an error if the delay doesn't fit in 2^16 cycles of the timer clock.
+    pub fn set_count(&mut self, delay: Microseconds) -> Result<(), PeriodError> {
+        let count = time::period_count(self.freq, delay)?;
         let timer = unsafe { T::steal() };
         timer.set_ccrn(count);
         timer.ccifg_clr();
+        Ok(())
     }
 
     #[inline]
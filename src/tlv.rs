@@ -0,0 +1,258 @@
+//! Device descriptor (TLV) access
+//!
+//! The TLV area at 0x1A00 holds factory data: the device ID and revisions, a die record that
+//! uniquely identifies the chip, and ADC and reference calibrations. It starts with a fixed
+//! header, followed by tag-length-value entries. `Tlv::new` checks the area against the CRC in the
+//! header before any of it is handed out.
+
+use crate::crc::crc16;
+use core::slice;
+
+// Start of the TLV area
+const TLV_START: usize = 0x1A00;
+// Start of the CRC-protected region and of the fixed header fields after the CRC
+const CRC_START: usize = 4;
+// First tag-length-value entry
+const ENTRIES_START: usize = 8;
+
+// Entry tags. Some devices tag their ADC calibration as ADC12 instead of ADC.
+const TAG_DIE_RECORD: u8 = 0x08;
+const TAG_ADC12_CAL: u8 = 0x11;
+const TAG_REF_CAL: u8 = 0x12;
+const TAG_ADC_CAL: u8 = 0x13;
+const TAG_END: u8 = 0xFF;
+
+/// Error verifying the TLV area
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlvError {
+    /// The computed CRC doesn't match the one stored in the header
+    CrcMismatch {
+        /// CRC stored in the header
+        stored: u16,
+        /// CRC computed over the TLV area
+        computed: u16,
+    },
+}
+
+/// Verified view of the TLV area
+pub struct Tlv {
+    bytes: &'static [u8],
+}
+
+impl Tlv {
+    /// Verifies the CRC of the TLV area
+    pub fn new() -> Result<Self, TlvError> {
+        let tlv = unsafe { Self::new_unchecked() };
+        let stored = tlv.u16_at(2);
+        let computed = crc16(&tlv.bytes[CRC_START..]);
+        if stored == computed {
+            Ok(tlv)
+        } else {
+            Err(TlvError::CrcMismatch { stored, computed })
+        }
+    }
+
+    /// Creates a view of the TLV area without checking its CRC
+    ///
+    /// # Safety
+    ///
+    /// The data may be corrupted, in which case any of the values read from it can be wrong.
+    pub unsafe fn new_unchecked() -> Self {
+        let header = slice::from_raw_parts(TLV_START as *const u8, 2);
+        // The CRC length byte gives the size of the area as a power of 2 in 32-bit words
+        let len = 4usize << header[1].min(8);
+        Tlv {
+            bytes: slice::from_raw_parts(TLV_START as *const u8, len),
+        }
+    }
+
+    #[inline]
+    fn u16_at(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.bytes[offset], self.bytes[offset + 1]])
+    }
+
+    /// Device information from the fixed header
+    #[inline]
+    pub fn device_info(&self) -> DeviceInfo {
+        DeviceInfo {
+            device_id: self.u16_at(4),
+            hw_revision: self.bytes[6],
+            fw_revision: self.bytes[7],
+        }
+    }
+
+    /// Iterate over the tag-length-value entries
+    #[inline]
+    pub fn entries(&self) -> Entries {
+        Entries {
+            bytes: self.bytes,
+            pos: ENTRIES_START,
+        }
+    }
+
+    /// Data of the first entry with the given tag
+    pub fn find(&self, tag: u8) -> Option<&'static [u8]> {
+        self.entries().find(|e| e.tag == tag).map(|e| e.data)
+    }
+
+    /// Die record, whose lot, wafer, and die position form a unique device ID
+    pub fn die_record(&self) -> Option<DieRecord> {
+        let d = self.find(TAG_DIE_RECORD)?;
+        if d.len() < 10 {
+            return None;
+        }
+        Some(DieRecord {
+            lot_wafer_id: u32::from_le_bytes([d[0], d[1], d[2], d[3]]),
+            die_x: u16::from_le_bytes([d[4], d[5]]),
+            die_y: u16::from_le_bytes([d[6], d[7]]),
+            test_results: u16::from_le_bytes([d[8], d[9]]),
+        })
+    }
+
+    /// ADC gain and offset, and temperature sensor calibration points. The points for the 2.0 V
+    /// and 2.5 V references are only present on devices whose TLV holds them.
+    pub fn adc_calibration(&self) -> Option<AdcCalibration> {
+        let d = self
+            .find(TAG_ADC_CAL)
+            .or_else(|| self.find(TAG_ADC12_CAL))?;
+        Some(AdcCalibration {
+            gain: word(d, 0)?,
+            offset: word(d, 1)? as i16,
+            temp_30c: word(d, 2)?,
+            temp_105c: word(d, 3)?,
+            temp_2v0: temp_points(d, 4),
+            temp_2v5: temp_points(d, 6),
+        })
+    }
+
+    /// Internal reference calibration. The 2.0 V and 2.5 V factors are only present on devices
+    /// whose TLV holds them.
+    pub fn ref_calibration(&self) -> Option<RefCalibration> {
+        let d = self.find(TAG_REF_CAL)?;
+        Some(RefCalibration {
+            factor_1v5: word(d, 0)?,
+            factor_2v0: word(d, 1),
+            factor_2v5: word(d, 2),
+        })
+    }
+}
+
+// Little-endian word `index` of an entry, if the entry is long enough to hold it
+#[inline]
+fn word(d: &[u8], index: usize) -> Option<u16> {
+    let bytes = d.get(2 * index..2 * index + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+// Temperature sensor calibration points stored in words `index` and `index + 1` of an entry
+#[inline]
+fn temp_points(d: &[u8], index: usize) -> Option<TempCalibration> {
+    Some(TempCalibration {
+        temp_30c: word(d, index)?,
+        temp_105c: word(d, index + 1)?,
+    })
+}
+
+/// Device ID and revisions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// Device ID, 0x830C for the MSP430FR2355
+    pub device_id: u16,
+    /// Hardware revision
+    pub hw_revision: u8,
+    /// Firmware revision
+    pub fw_revision: u8,
+}
+
+/// Die record, which identifies a single chip
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DieRecord {
+    /// Lot and wafer ID
+    pub lot_wafer_id: u32,
+    /// X position of the die on the wafer
+    pub die_x: u16,
+    /// Y position of the die on the wafer
+    pub die_y: u16,
+    /// Factory test results
+    pub test_results: u16,
+}
+
+impl DieRecord {
+    /// Unique device ID made of the lot, wafer, and die position
+    #[inline]
+    pub fn unique_id(&self) -> [u8; 8] {
+        let mut id = [0; 8];
+        id[..4].copy_from_slice(&self.lot_wafer_id.to_le_bytes());
+        id[4..6].copy_from_slice(&self.die_x.to_le_bytes());
+        id[6..].copy_from_slice(&self.die_y.to_le_bytes());
+        id
+    }
+}
+
+/// ADC calibration values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdcCalibration {
+    /// Gain factor, where 0x8000 is unity gain
+    pub gain: u16,
+    /// Offset in ADC counts
+    pub offset: i16,
+    /// Temperature sensor result at 30 °C, with the 1.5 V reference at 12-bit resolution
+    pub temp_30c: u16,
+    /// Temperature sensor result at 105 °C, with the 1.5 V reference at 12-bit resolution
+    pub temp_105c: u16,
+    /// Temperature sensor results with the 2.0 V reference
+    pub temp_2v0: Option<TempCalibration>,
+    /// Temperature sensor results with the 2.5 V reference
+    pub temp_2v5: Option<TempCalibration>,
+}
+
+/// Temperature sensor calibration points for one reference voltage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TempCalibration {
+    /// Temperature sensor result at 30 °C, at 12-bit resolution
+    pub temp_30c: u16,
+    /// Temperature sensor result at 105 °C, at 12-bit resolution
+    pub temp_105c: u16,
+}
+
+/// Internal reference calibration values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RefCalibration {
+    /// Correction factor for the 1.5 V reference, where 0x8000 is unity
+    pub factor_1v5: u16,
+    /// Correction factor for the 2.0 V reference, where 0x8000 is unity
+    pub factor_2v0: Option<u16>,
+    /// Correction factor for the 2.5 V reference, where 0x8000 is unity
+    pub factor_2v5: Option<u16>,
+}
+
+/// A tag-length-value entry
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    /// Entry tag
+    pub tag: u8,
+    /// Entry value
+    pub data: &'static [u8],
+}
+
+/// Iterator over the tag-length-value entries
+pub struct Entries {
+    bytes: &'static [u8],
+    pos: usize,
+}
+
+impl Iterator for Entries {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        let tag = *self.bytes.get(self.pos)?;
+        let len = *self.bytes.get(self.pos + 1)? as usize;
+        if tag == TAG_END {
+            return None;
+        }
+        let start = self.pos + 2;
+        let data = self.bytes.get(start..start + len)?;
+        self.pos = start + len;
+        Some(Entry { tag, data })
+    }
+}
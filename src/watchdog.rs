@@ -3,8 +3,13 @@
 //! **Note**: MSP430 devices will reset after bootup if watchdog is not stopped after an initial 32
 //! ms interval (roughly). If this is undesirable, call `Wdt::constrain()` as soon in the
 //! application as possible to stop the watchdog.
+//!
+//! The watchdog only supports a fixed set of timeouts, each a power-of-two number of clock
+//! periods. Requested durations are rounded up to the shortest supported timeout that is at least
+//! as long, based on the frequency of the selected clock source.
 
-use crate::clock::{Aclk, Smclk};
+use crate::clock::{Aclk, Clock, Smclk, VLOCLK};
+use crate::time::{self, Hertz, Microseconds};
 use core::marker::PhantomData;
 use embedded_hal::timer::{Cancel, CountDown, Periodic};
 use embedded_hal::watchdog::{Watchdog, WatchdogDisable, WatchdogEnable};
@@ -25,12 +30,14 @@ mod sealed {
 }
 
 /// Watchdog timer which can be configured to watchdog or interval (timer) mode
-pub struct Wdt<MODE> {
+pub struct Wdt<'a, MODE> {
     _mode: PhantomData<MODE>,
+    _clk: PhantomData<&'a ()>,
     periph: pac::WDT_A,
+    clk_freq: Hertz,
 }
 
-impl Wdt<WatchdogMode> {
+impl Wdt<'_, WatchdogMode> {
     /// Convert WDT peripheral into a watchdog timer (watchdog mode) and disable the watchdog. Set
     /// clock source to VLOCLK.
     pub fn constrain(wdt: pac::WDT_A) -> Self {
@@ -44,7 +51,9 @@ impl Wdt<WatchdogMode> {
         });
         Wdt {
             _mode: PhantomData,
+            _clk: PhantomData,
             periph: wdt,
+            clk_freq: Hertz::from_raw(VLOCLK as u32),
         }
     }
 }
@@ -74,7 +83,7 @@ impl WatchdogSelect for IntervalMode {
 
 type WdtWriter = pac::wdt_a::wdtctl::W;
 
-impl<MODE: WatchdogSelect> Wdt<MODE> {
+impl<'a, MODE: WatchdogSelect> Wdt<'a, MODE> {
     #[inline(always)]
     fn prewrite(w: &mut WdtWriter, bits: u16) -> &mut WdtWriter {
         // Write argument bits, password, and correct mode bit to the watchdog write proxy
@@ -84,7 +93,8 @@ impl<MODE: WatchdogSelect> Wdt<MODE> {
     }
 
     #[inline]
-    fn set_clk(&mut self, clk_src: WDTSSEL_A) -> &mut Self {
+    fn set_clk(&mut self, clk_src: WDTSSEL_A, clk_freq: Hertz) -> &mut Self {
+        self.clk_freq = clk_freq;
         // Halt timer first, as specified in the user's guide
         self.periph.wdtctl.write(|w| {
             Self::prewrite(w, 0)
@@ -107,25 +117,44 @@ impl<MODE: WatchdogSelect> Wdt<MODE> {
 
     /// Set watchdog clock source to ACLK and halt timer.
     #[inline]
-    pub fn set_aclk(&mut self, _clks: &Aclk) -> &mut Self {
-        self.set_clk(WDTSSEL_A::ACLK)
+    pub fn set_aclk(&mut self, aclk: &'a Aclk) -> &mut Self {
+        self.set_clk(WDTSSEL_A::ACLK, aclk.freq())
     }
 
     /// Set watchdog clock source to VLOCLK and halt timer.
     #[inline]
     pub fn set_vloclk(&mut self) -> &mut Self {
-        self.set_clk(WDTSSEL_A::VLOCLK)
+        self.set_clk(WDTSSEL_A::VLOCLK, Hertz::from_raw(VLOCLK as u32))
     }
 
     /// Set watchdog clock source to SMCLK and halt timer.
     #[inline]
-    pub fn set_smclk(&mut self, _clks: &Smclk) -> &mut Self {
-        self.set_clk(WDTSSEL_A::SMCLK)
+    pub fn set_smclk(&mut self, smclk: &'a Smclk) -> &mut Self {
+        self.set_clk(WDTSSEL_A::SMCLK, smclk.freq())
+    }
+
+    // Shortest supported timeout that lasts at least `duration` with the current clock source
+    #[inline]
+    fn periods_for(&self, duration: Microseconds) -> WdtClkPeriods {
+        let cycles = time::cycles(self.clk_freq, duration);
+        [
+            (6, WdtClkPeriods::_64),
+            (9, WdtClkPeriods::_512),
+            (13, WdtClkPeriods::_8192),
+            (15, WdtClkPeriods::_32K),
+            (19, WdtClkPeriods::_512K),
+            (23, WdtClkPeriods::_8192K),
+            (27, WdtClkPeriods::_128M),
+        ]
+        .iter()
+        .find(|(shift, _)| cycles <= 1 << shift)
+        .map_or(WdtClkPeriods::_2G, |&(_, periods)| periods)
     }
 
     // Reset countdown, unpause timer, and set timeout in a single write
     #[inline]
-    fn unpause_and_set_time(&mut self, periods: WdtClkPeriods) {
+    fn unpause_and_set_time(&mut self, duration: Microseconds) {
+        let periods = self.periods_for(duration);
         self.periph.wdtctl.modify(|r, w| {
             Self::prewrite(w, r.bits())
                 .wdtcntcl()
@@ -146,7 +175,7 @@ impl<MODE: WatchdogSelect> Wdt<MODE> {
     }
 }
 
-impl Watchdog for Wdt<WatchdogMode> {
+impl Watchdog for Wdt<'_, WatchdogMode> {
     #[inline]
     fn feed(&mut self) {
         self.periph
@@ -155,8 +184,8 @@ impl Watchdog for Wdt<WatchdogMode> {
     }
 }
 
-impl WatchdogEnable for Wdt<WatchdogMode> {
-    type Time = WdtClkPeriods;
+impl WatchdogEnable for Wdt<'_, WatchdogMode> {
+    type Time = Microseconds;
 
     #[inline]
     fn start<T>(&mut self, period: T)
@@ -167,15 +196,15 @@ impl WatchdogEnable for Wdt<WatchdogMode> {
     }
 }
 
-impl WatchdogDisable for Wdt<WatchdogMode> {
+impl WatchdogDisable for Wdt<'_, WatchdogMode> {
     #[inline]
     fn disable(&mut self) {
         self.pause();
     }
 }
 
-impl CountDown for Wdt<IntervalMode> {
-    type Time = WdtClkPeriods;
+impl CountDown for Wdt<'_, IntervalMode> {
+    type Time = Microseconds;
 
     #[inline]
     fn start<T>(&mut self, count: T)
@@ -198,7 +227,7 @@ impl CountDown for Wdt<IntervalMode> {
     }
 }
 
-impl Cancel for Wdt<IntervalMode> {
+impl Cancel for Wdt<'_, IntervalMode> {
     type Error = void::Void;
 
     /// This implementation will never return error even if watchdog has already been paused, hence
@@ -210,15 +239,17 @@ impl Cancel for Wdt<IntervalMode> {
     }
 }
 
-impl Periodic for Wdt<IntervalMode> {}
+impl Periodic for Wdt<'_, IntervalMode> {}
 
-impl Wdt<WatchdogMode> {
+impl<'a> Wdt<'a, WatchdogMode> {
     /// Convert to interval mode and pause timer
     #[inline]
-    pub fn to_interval(self) -> Wdt<IntervalMode> {
+    pub fn to_interval(self) -> Wdt<'a, IntervalMode> {
         let mut wdt = Wdt {
             _mode: PhantomData,
+            _clk: PhantomData,
             periph: self.periph,
+            clk_freq: self.clk_freq,
         };
         // Change mode bit and pause timer
         wdt.pause();
@@ -226,13 +257,15 @@ impl Wdt<WatchdogMode> {
     }
 }
 
-impl Wdt<IntervalMode> {
+impl<'a> Wdt<'a, IntervalMode> {
     /// Convert to watchdog mode and pause timer
     #[inline]
-    pub fn to_watchdog(self) -> Wdt<WatchdogMode> {
+    pub fn to_watchdog(self) -> Wdt<'a, WatchdogMode> {
         let mut wdt = Wdt {
             _mode: PhantomData,
+            _clk: PhantomData,
             periph: self.periph,
+            clk_freq: self.clk_freq,
         };
         // Change mode bit and pause timer
         wdt.pause();